@@ -34,8 +34,33 @@ pub fn bench_push(c: &mut Criterion) {
     }
 }
 
+pub fn bench_from_words(c: &mut Criterion) {
+    let n = 1 << 24;
+    for p in PERC {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        let mut words = Vec::with_capacity((n / 64) as usize);
+        for _ in 0..n / 64 {
+            let mut word = 0;
+            for i in 0..64 {
+                word |= (rng.gen_bool(p) as u64) << i;
+            }
+            words.push(word);
+        }
+
+        let mut g = c.benchmark_group("from_words");
+        g.throughput(Throughput::Elements(n));
+        let parameter = format!("N={}, %={}", n, p * 100.0);
+        g.bench_with_input(BenchmarkId::new("seq", &parameter), &words, |b, words| {
+            b.iter_with_large_drop(|| BitVector::from_words(words, n))
+        });
+        g.bench_with_input(BenchmarkId::new("par", &parameter), &words, |b, words| {
+            b.iter_with_large_drop(|| BitVector::from_words_parallel(words, n))
+        });
+    }
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default();
-    targets = bench_push);
+    targets = bench_push, bench_from_words);
 criterion_main!(benches);
@@ -54,6 +54,35 @@ pub fn bench_iter_fold(c: &mut Criterion) {
     }
 }
 
+fn make_clustered_bitvec(rng: &mut impl Rng, n: u64, max_run: u64) -> BitVector {
+    let mut bv = BitVector::with_capacity(n);
+    let mut b = false;
+    while bv.len() < n {
+        let len = rng.gen_range(1, max_run).min(n - bv.len());
+        for _ in 0..len {
+            bv.push(b);
+        }
+        b = !b;
+    }
+    bv
+}
+
+pub fn bench_iter_runs(c: &mut Criterion) {
+    for n in SIZES {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        let bv = make_clustered_bitvec(&mut rng, n, 1 << 12);
+
+        let mut g = c.benchmark_group("iter_runs");
+        g.throughput(Throughput::Elements(bv.len()));
+        g.bench_with_input(BenchmarkId::new("runs", n), &bv, |b, bv| {
+            b.iter(|| bv.iter_runs().fold(0, |sum, (_, _, len)| sum + len))
+        });
+        g.bench_with_input(BenchmarkId::new("bits", n), &bv, |b, bv| {
+            b.iter(|| bv.iter().fold(0, |sum, b| sum + b as u64))
+        });
+    }
+}
+
 pub fn bench_rank1(c: &mut Criterion) {
     for n in SIZES {
         for p in PERC {
@@ -160,6 +189,7 @@ criterion_group!(
     config = Criterion::default().sample_size(200);
     targets =
     bench_iter_fold,
+    bench_iter_runs,
     bench_rank1,
     bench_select1,
     bench_select0_raw
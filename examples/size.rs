@@ -32,5 +32,6 @@ fn main() {
             "n = {}, p = {}: {} bytes ({} bit / orig bit)",
             n, p, size, rate
         );
+        println!("{}\n", bv.space_report());
     }
 }
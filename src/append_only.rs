@@ -0,0 +1,378 @@
+use crate::bit_vector::{BitVector, LBLOCK_WIDTH};
+use crate::fid::FID;
+use arc_swap::ArcSwap;
+use std::sync::Arc;
+
+/// An immutable prefix of a [`BitVectorWriter`], made of frozen segments.
+///
+/// Segments are shared between snapshots, so publishing only encodes the new bits.
+/// Each segment is more than twice as long as the next, which keeps their number logarithmic.
+#[derive(Debug, Default, Clone)]
+pub struct BitVectorSnapshot {
+    len: u64,
+    ones: u64,
+    segments: Vec<Arc<BitVector>>,
+    /// Start position of each segment.
+    starts: Vec<u64>,
+    /// Number of 1s before each segment.
+    ranks: Vec<u64>,
+}
+
+impl BitVectorSnapshot {
+    /// Returns the number of frozen segments.
+    pub fn segment_len(&self) -> usize {
+        self.segments.len()
+    }
+
+    fn with_segment(&self, mut segment: BitVector) -> Self {
+        let mut segments = self.segments.clone();
+
+        // Merge until each segment is more than twice as long as the next.
+        while let Some(last) = segments.last() {
+            if last.len() > 2 * segment.len() {
+                break;
+            }
+            let mut merged = BitVector::clone(last);
+            merged.append(&segment);
+            segment = merged;
+            segments.pop();
+        }
+        segment.shrink_to_fit();
+        segments.push(Arc::new(segment));
+
+        let mut snapshot = BitVectorSnapshot::default();
+        for segment in segments {
+            snapshot.starts.push(snapshot.len);
+            snapshot.ranks.push(snapshot.ones);
+            snapshot.len += segment.len();
+            snapshot.ones += segment.rank1(segment.len());
+            snapshot.segments.push(segment);
+        }
+        snapshot
+    }
+
+    /// Returns the segment containing position `i`.
+    fn find_segment(&self, i: u64) -> usize {
+        self.starts.partition_point(|&start| start <= i) - 1
+    }
+}
+
+impl FID for BitVectorSnapshot {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn rank1(&self, i: u64) -> u64 {
+        if self.len <= i {
+            return self.ones;
+        }
+        let k = self.find_segment(i);
+        self.ranks[k] + self.segments[k].rank1(i - self.starts[k])
+    }
+
+    fn select(&self, b: bool, r: u64) -> u64 {
+        if b {
+            self.select1(r)
+        } else {
+            self.select0(r)
+        }
+    }
+
+    fn select0(&self, r: u64) -> u64 {
+        if self.len - self.ones <= r {
+            return self.len;
+        }
+        let zeros = |k: usize| self.starts[k] - self.ranks[k];
+        let (mut s, mut e) = (0, self.segments.len());
+        while e - s > 1 {
+            let m = (s + e) / 2;
+            if zeros(m) <= r {
+                s = m;
+            } else {
+                e = m;
+            }
+        }
+        self.starts[s] + self.segments[s].select0(r - zeros(s))
+    }
+
+    fn select1(&self, r: u64) -> u64 {
+        if self.ones <= r {
+            return self.len;
+        }
+        let k = self.ranks.partition_point(|&rank| rank <= r) - 1;
+        self.starts[k] + self.segments[k].select1(r - self.ranks[k])
+    }
+
+    fn get(&self, i: u64) -> bool {
+        let k = self.find_segment(i);
+        self.segments[k].get(i - self.starts[k])
+    }
+
+    fn get_slice(&self, i: u64, size: u64) -> u64 {
+        debug_assert!(size <= 64);
+
+        let slice_end = i + size;
+        assert!(slice_end <= self.len);
+        if size == 0 {
+            return 0;
+        }
+
+        let k = self.find_segment(i);
+        let p = i - self.starts[k];
+        let lo_size = size.min(self.segments[k].len() - p);
+        let lo_bits = self.segments[k].get_slice(p, lo_size);
+        if lo_size == size {
+            return lo_bits;
+        }
+        let hi_bits = self.segments[k + 1].get_slice(0, size - lo_size);
+        lo_bits | hi_bits << lo_size
+    }
+
+    fn has_fast_get_slice(&self) -> bool {
+        true
+    }
+}
+
+/// The writing end of an append-only [`BitVector`] shared across threads.
+///
+/// Bits are appended to a private tail. [`publish`] freezes all complete
+/// large blocks of the tail into an immutable [`BitVectorSnapshot`],
+/// which readers pick up with [`BitVectorReader::refresh`].
+///
+/// # Examples
+///
+/// ```
+/// # use fid::{BitVectorWriter, FID};
+/// let mut writer = BitVectorWriter::new();
+/// let mut reader = writer.reader();
+/// for i in 0..5000 {
+///     writer.push(i % 3 == 0);
+/// }
+/// assert_eq!(writer.publish(), 4096);
+///
+/// assert!(reader.refresh());
+/// assert_eq!(reader.len(), 4096);
+/// assert_eq!(reader.rank1(4096), 1366);
+/// ```
+///
+/// [`publish`]: BitVectorWriter::publish
+#[derive(Debug, Default)]
+pub struct BitVectorWriter {
+    /// Bits after the published prefix.
+    tail: BitVector,
+    published: Arc<BitVectorSnapshot>,
+    shared: Arc<ArcSwap<BitVectorSnapshot>>,
+}
+
+impl BitVectorWriter {
+    /// Constructs a new, empty [`BitVectorWriter`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a new reader of the published prefix.
+    pub fn reader(&self) -> BitVectorReader {
+        BitVectorReader {
+            snapshot: self.published.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Returns the total number of bits, including unpublished bits.
+    pub fn len(&self) -> u64 {
+        self.published.len() + self.tail.len()
+    }
+
+    /// Returns true if no bits were appended.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of published bits.
+    pub fn published_len(&self) -> u64 {
+        self.published.len()
+    }
+
+    /// Appends a bit at the end of the vector.
+    pub fn push(&mut self, b: bool) {
+        self.tail.push(b);
+    }
+
+    /// Appends `size` bits from `slice` at the end of the vector.
+    ///
+    /// # Panics
+    /// * `size` is greater than 64.
+    pub fn push_slice(&mut self, slice: u64, size: u64) {
+        self.tail.push_slice(slice, size);
+    }
+
+    /// Publishes all complete large blocks and returns the number of published bits.
+    ///
+    /// Encoded blocks are not re-encoded, but segments may be merged,
+    /// so publishing costs `O(log n)` amortized per bit.
+    pub fn publish(&mut self) -> u64 {
+        let len = self.tail.len();
+        let tail = self.tail.split_off(len - len % LBLOCK_WIDTH);
+        let segment = std::mem::replace(&mut self.tail, tail);
+        if !segment.is_empty() {
+            self.published = Arc::new(self.published.with_segment(segment));
+            self.shared.store(self.published.clone());
+        }
+        self.published.len()
+    }
+}
+
+/// The reading end of a [`BitVectorWriter`].
+///
+/// Queries go to the snapshot taken at the last [`refresh`] and never take locks.
+///
+/// [`refresh`]: BitVectorReader::refresh
+#[derive(Debug, Clone)]
+pub struct BitVectorReader {
+    snapshot: Arc<BitVectorSnapshot>,
+    shared: Arc<ArcSwap<BitVectorSnapshot>>,
+}
+
+impl BitVectorReader {
+    /// Picks up the latest published snapshot without taking a lock.
+    /// Returns true if it is different from the previous one.
+    pub fn refresh(&mut self) -> bool {
+        let latest = self.shared.load_full();
+        let changed = !Arc::ptr_eq(&latest, &self.snapshot);
+        self.snapshot = latest;
+        changed
+    }
+
+    /// Returns the current snapshot.
+    pub fn snapshot(&self) -> &Arc<BitVectorSnapshot> {
+        &self.snapshot
+    }
+}
+
+impl FID for BitVectorReader {
+    fn len(&self) -> u64 {
+        self.snapshot.len()
+    }
+
+    fn rank1(&self, i: u64) -> u64 {
+        self.snapshot.rank1(i)
+    }
+
+    fn select(&self, b: bool, r: u64) -> u64 {
+        self.snapshot.select(b, r)
+    }
+
+    fn select0(&self, r: u64) -> u64 {
+        self.snapshot.select0(r)
+    }
+
+    fn select1(&self, r: u64) -> u64 {
+        self.snapshot.select1(r)
+    }
+
+    fn get(&self, i: u64) -> bool {
+        self.snapshot.get(i)
+    }
+
+    fn get_slice(&self, i: u64, size: u64) -> u64 {
+        self.snapshot.get_slice(i, size)
+    }
+
+    fn get_word(&self, i: u64, size: u64) -> u64 {
+        self.snapshot.get_word(i, size)
+    }
+
+    fn has_fast_get_slice(&self) -> bool {
+        self.snapshot.has_fast_get_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, StdRng};
+
+    fn check_prefix(reader: &BitVectorReader, bv: &BitVector) {
+        let n = reader.len();
+        assert_eq!(reader.rank1(n), bv.rank1(n));
+        for i in (0..n).step_by(17) {
+            assert_eq!(reader.rank1(i), bv.rank1(i));
+            assert_eq!(reader.get(i), bv.get(i));
+        }
+        for i in (0..n.saturating_sub(64)).step_by(61) {
+            assert_eq!(reader.get_slice(i, 64), bv.get_slice(i, 64));
+        }
+        let ones = reader.rank1(n);
+        for r in (0..ones).step_by(7) {
+            assert_eq!(reader.select1(r), bv.select1(r));
+        }
+        for r in (0..n - ones).step_by(7) {
+            assert_eq!(reader.select0(r), bv.select0(r));
+        }
+    }
+
+    #[test]
+    fn publish() {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        let mut writer = BitVectorWriter::new();
+        let mut reader = writer.reader();
+        let mut bv = BitVector::new();
+
+        for _ in 0..25 {
+            let n = rng.gen_range(0, 10_000);
+            let p = rng.gen_range(0.0, 1.0);
+            for _ in 0..n {
+                let b = rng.gen_bool(p);
+                writer.push(b);
+                bv.push(b);
+            }
+            let published = writer.publish();
+            assert_eq!(published, bv.len() - bv.len() % 1024);
+            assert_eq!(writer.len(), bv.len());
+
+            reader.refresh();
+            assert_eq!(reader.len(), published);
+            assert!(
+                reader.snapshot().segment_len() <= 64 - (published / 1024).leading_zeros() as usize
+            );
+            check_prefix(&reader, &bv);
+        }
+    }
+
+    #[test]
+    fn concurrent() {
+        let bit = |i: u64| i.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 61 == 0;
+        let n = 100_000;
+
+        let mut writer = BitVectorWriter::new();
+        let mut reader = writer.reader();
+        std::thread::scope(|s| {
+            let handle = s.spawn(move || {
+                for i in 0..n {
+                    writer.push(bit(i));
+                    if i.is_multiple_of(5000) {
+                        writer.publish();
+                    }
+                }
+                writer.publish();
+            });
+
+            loop {
+                // Nothing is published after the writer finishes, even if it panics.
+                let finished = handle.is_finished();
+                if !reader.refresh() {
+                    if finished {
+                        break;
+                    }
+                    std::thread::yield_now();
+                    continue;
+                }
+                let len = reader.len();
+                let ones = (0..len).filter(|&i| bit(i)).count() as u64;
+                assert_eq!(reader.rank1(len), ones);
+            }
+            handle.join().unwrap();
+        });
+        assert_eq!(reader.len(), n - n % 1024);
+    }
+}
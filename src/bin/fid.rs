@@ -0,0 +1,473 @@
+//! Command-line tool to build, inspect and query bit vector files.
+//!
+//! Files are [`BitVector`]s serialized with `bincode`.
+
+use fid::{BitVector, FID};
+use std::fs;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage:
+  fid build <ascii|words|positions> <INPUT> <OUTPUT> [--len N]
+  fid stats <FILE>
+  fid query <FILE> [QUERY]...
+  fid help
+
+Input formats for `build` (INPUT may be `-` for stdin):
+  ascii      characters `0` and `1`, whitespace is ignored
+  words      packed little-endian 64-bit words
+  positions  whitespace-separated positions of 1s
+`--len` sets the length for `words` and `positions`, which defaults to
+all bits of the words, or one past the last position.
+
+Queries (read one per line from stdin when none are given):
+  rank0 I, rank1 I      number of 0s or 1s in [0, I)
+  select0 R, select1 R  position of the (R + 1)-th 0 or 1, or the length if none
+  get I                 bit at position I as 0 or 1
+  count START END       number of 1s in [START, END)
+";
+
+type Result<T> = std::result::Result<T, String>;
+
+/// Maximum length built from positions, which bounds the words allocated for them.
+const MAX_POSITIONS_LEN: u64 = 1 << 36;
+
+/// A parsed command line.
+#[derive(Debug, PartialEq)]
+enum Command {
+    Build {
+        format: Format,
+        input: String,
+        output: String,
+        len: Option<u64>,
+    },
+    Stats {
+        file: String,
+    },
+    Query {
+        file: String,
+        queries: Vec<String>,
+    },
+    Help,
+}
+
+/// Input format for `build`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    Words,
+    Positions,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = parse_args(&args)
+        .and_then(|command| run(command, &mut io::stdin().lock(), &mut io::stdout().lock()));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            eprintln!("run `fid help` for usage");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: &[&str]) -> Result<Command> {
+    match args {
+        ["build", format, input, output, rest @ ..] => {
+            let format = match *format {
+                "ascii" => Format::Ascii,
+                "words" => Format::Words,
+                "positions" => Format::Positions,
+                _ => return Err(format!("unknown input format `{format}`")),
+            };
+            let len = match rest {
+                [] => None,
+                ["--len", len] => Some(parse_num(len)?),
+                _ => return Err(format!("unexpected arguments: {}", rest.join(" "))),
+            };
+            if format == Format::Ascii && len.is_some() {
+                return Err("`--len` is not supported for ascii input".to_string());
+            }
+            Ok(Command::Build {
+                format,
+                input: input.to_string(),
+                output: output.to_string(),
+                len,
+            })
+        }
+        ["stats", file] => Ok(Command::Stats {
+            file: file.to_string(),
+        }),
+        ["query", file, queries @ ..] => Ok(Command::Query {
+            file: file.to_string(),
+            queries: queries.iter().map(|q| q.to_string()).collect(),
+        }),
+        ["help"] | ["--help"] | ["-h"] => Ok(Command::Help),
+        [] => Err("missing command".to_string()),
+        [command, ..] => Err(format!("invalid arguments for `{command}`")),
+    }
+}
+
+/// Runs `command`, reading `-` inputs and queries from `stdin`.
+fn run(command: Command, stdin: &mut dyn BufRead, out: &mut dyn Write) -> Result<()> {
+    match command {
+        Command::Build {
+            format,
+            input,
+            output,
+            len,
+        } => {
+            let data = read_input(&input, stdin)?;
+            let bv = build(format, &data, len)?;
+            let bytes = bincode::serialize(&bv).map_err(|err| err.to_string())?;
+            fs::write(&output, bytes).map_err(|err| format!("cannot write `{output}`: {err}"))?;
+            writeln!(
+                out,
+                "wrote {} bits ({} ones) to {output}",
+                bv.len(),
+                bv.rank1(bv.len())
+            )
+            .map_err(|err| err.to_string())
+        }
+        Command::Stats { file } => {
+            writeln!(out, "{}", load(&file)?.space_report()).map_err(|err| err.to_string())
+        }
+        Command::Query { file, queries } => {
+            let queries: Vec<&str> = queries.iter().map(String::as_str).collect();
+            query(&load(&file)?, &queries, stdin, out)
+        }
+        Command::Help => write!(out, "{USAGE}").map_err(|err| err.to_string()),
+    }
+}
+
+fn build(format: Format, data: &[u8], len: Option<u64>) -> Result<BitVector> {
+    match format {
+        Format::Ascii => parse_ascii(data),
+        Format::Words => parse_words(data, len),
+        Format::Positions => parse_positions(data, len),
+    }
+}
+
+fn read_input(input: &str, stdin: &mut dyn BufRead) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    if input == "-" {
+        stdin
+            .read_to_end(&mut data)
+            .map_err(|err| format!("cannot read stdin: {err}"))?;
+    } else {
+        data = fs::read(input).map_err(|err| format!("cannot read `{input}`: {err}"))?;
+    }
+    Ok(data)
+}
+
+fn parse_ascii(data: &[u8]) -> Result<BitVector> {
+    let mut bv = BitVector::new();
+    for (offset, &c) in data.iter().enumerate() {
+        match c {
+            b'0' => bv.push(false),
+            b'1' => bv.push(true),
+            c if c.is_ascii_whitespace() => {}
+            _ => return Err(format!("invalid character at byte {offset}")),
+        }
+    }
+    Ok(bv)
+}
+
+fn parse_words(data: &[u8], len: Option<u64>) -> Result<BitVector> {
+    if !data.len().is_multiple_of(8) {
+        return Err("word input is not a multiple of 8 bytes".to_string());
+    }
+    let words: Vec<u64> = data
+        .chunks_exact(8)
+        .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    let max_len = words.len() as u64 * 64;
+    let len = len.unwrap_or(max_len);
+    if len > max_len {
+        return Err(format!("length {len} exceeds the {max_len} bits of input"));
+    }
+    Ok(BitVector::from_words(&words, len))
+}
+
+fn parse_positions(data: &[u8], len: Option<u64>) -> Result<BitVector> {
+    let text = std::str::from_utf8(data).map_err(|err| err.to_string())?;
+    let positions = text
+        .split_ascii_whitespace()
+        .map(parse_num)
+        .collect::<Result<Vec<u64>>>()?;
+
+    let end = match positions.iter().max() {
+        Some(&p) => p
+            .checked_add(1)
+            .ok_or_else(|| format!("position {p} is out of range"))?,
+        None => 0,
+    };
+    let len = len.unwrap_or(end);
+    if end > len {
+        return Err(format!("position {} is out of range", end - 1));
+    }
+    if len > MAX_POSITIONS_LEN {
+        return Err(format!(
+            "length {len} exceeds the maximum of {MAX_POSITIONS_LEN} bits for positions"
+        ));
+    }
+    let mut words = vec![0u64; len.div_ceil(64) as usize];
+    for p in positions {
+        words[(p / 64) as usize] |= 1 << (p % 64);
+    }
+    Ok(BitVector::from_words(&words, len))
+}
+
+fn load(file: &str) -> Result<BitVector> {
+    let bytes = fs::read(file).map_err(|err| format!("cannot read `{file}`: {err}"))?;
+    bincode::deserialize(&bytes).map_err(|err| format!("cannot load `{file}`: {err}"))
+}
+
+fn query(
+    bv: &BitVector,
+    args: &[&str],
+    stdin: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let mut out = BufWriter::new(out);
+    if args.is_empty() {
+        for (line_pos, line) in stdin.lines().enumerate() {
+            let line = line.map_err(|err| format!("cannot read stdin: {err}"))?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut tokens = line.split_ascii_whitespace();
+            let answer = answer_query(bv, &mut tokens)
+                .map_err(|err| format!("line {}: {err}", line_pos + 1))?;
+            if tokens.next().is_some() {
+                return Err(format!("line {}: trailing arguments", line_pos + 1));
+            }
+            writeln!(out, "{answer}").map_err(|err| err.to_string())?;
+        }
+    } else {
+        let mut tokens = args.iter().copied().peekable();
+        while tokens.peek().is_some() {
+            let answer = answer_query(bv, &mut tokens)?;
+            writeln!(out, "{answer}").map_err(|err| err.to_string())?;
+        }
+    }
+    out.flush().map_err(|err| err.to_string())
+}
+
+fn answer_query<'a, I>(bv: &BitVector, tokens: &mut I) -> Result<u64>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let op = tokens.next().ok_or("missing query")?;
+    let mut arg = || -> Result<u64> {
+        let arg = tokens
+            .next()
+            .ok_or(format!("missing argument for `{op}`"))?;
+        parse_num(arg)
+    };
+    let check_pos = |i: u64, end: u64| {
+        if i > end {
+            Err(format!("position {i} is out of range"))
+        } else {
+            Ok(i)
+        }
+    };
+
+    let len = bv.len();
+    match op {
+        "rank0" => Ok(bv.rank0(check_pos(arg()?, len)?)),
+        "rank1" => Ok(bv.rank1(check_pos(arg()?, len)?)),
+        "select0" => Ok(bv.select0(arg()?)),
+        "select1" => Ok(bv.select1(arg()?)),
+        "get" => {
+            let i = arg()?;
+            if i >= len {
+                return Err(format!("position {i} is out of range"));
+            }
+            Ok(bv.get(i) as u64)
+        }
+        "count" => {
+            let start = arg()?;
+            let end = check_pos(arg()?, len)?;
+            Ok(bv.count_ones(check_pos(start, end)?..end))
+        }
+        _ => Err(format!("unknown query `{op}`")),
+    }
+}
+
+fn parse_num(s: &str) -> Result<u64> {
+    s.parse().map_err(|_| format!("invalid number `{s}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<&str> {
+        line.split_whitespace().collect()
+    }
+
+    /// Runs `command` with `stdin` and returns the output.
+    fn run_with(command: Command, stdin: &str) -> Result<String> {
+        let mut out = Vec::new();
+        run(command, &mut stdin.as_bytes(), &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    /// Returns a path in the temporary directory unique to this test.
+    fn temp_file(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("fid-cli-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name).to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn parse_build() {
+        assert_eq!(
+            parse_args(&args("build words in.bin out.fid --len 100")),
+            Ok(Command::Build {
+                format: Format::Words,
+                input: "in.bin".to_string(),
+                output: "out.fid".to_string(),
+                len: Some(100),
+            })
+        );
+        assert_eq!(
+            parse_args(&args("build positions - out.fid")),
+            Ok(Command::Build {
+                format: Format::Positions,
+                input: "-".to_string(),
+                output: "out.fid".to_string(),
+                len: None,
+            })
+        );
+        assert!(parse_args(&args("build ascii in out --len 3")).is_err());
+        assert!(parse_args(&args("build hex in out")).is_err());
+        assert!(parse_args(&args("build words in out --len x")).is_err());
+        assert!(parse_args(&args("build words in out --size 3")).is_err());
+        assert!(parse_args(&args("build words in")).is_err());
+    }
+
+    #[test]
+    fn parse_other_commands() {
+        assert_eq!(
+            parse_args(&args("stats a.fid")),
+            Ok(Command::Stats {
+                file: "a.fid".to_string()
+            })
+        );
+        assert_eq!(
+            parse_args(&args("query a.fid rank1 3 get 2")),
+            Ok(Command::Query {
+                file: "a.fid".to_string(),
+                queries: vec!["rank1", "3", "get", "2"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            })
+        );
+        for help in ["help", "--help", "-h"] {
+            assert_eq!(parse_args(&[help]), Ok(Command::Help));
+        }
+        assert!(parse_args(&[]).is_err());
+        assert!(parse_args(&args("stats")).is_err());
+        assert!(parse_args(&args("stats a b")).is_err());
+        assert!(parse_args(&args("frobnicate")).is_err());
+    }
+
+    #[test]
+    fn build_formats() {
+        let expected = BitVector::from([true, false, true, true, false].as_slice());
+        assert_eq!(
+            build(Format::Ascii, b"10 11\n0", None),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            build(Format::Positions, b"0 2\n3", Some(5)),
+            Ok(expected.clone())
+        );
+        let words = 0b01101u64.to_le_bytes();
+        assert_eq!(build(Format::Words, &words, Some(5)), Ok(expected));
+
+        assert_eq!(build(Format::Words, &words, None).unwrap().len(), 64);
+        assert_eq!(build(Format::Positions, b"7", None).unwrap().len(), 8);
+        assert!(build(Format::Ascii, b"102", None).is_err());
+        assert!(build(Format::Words, &[0; 7], None).is_err());
+        assert!(build(Format::Words, &words, Some(65)).is_err());
+        assert!(build(Format::Positions, b"7", Some(7)).is_err());
+        assert!(build(Format::Positions, b"a", None).is_err());
+
+        let max = u64::MAX.to_string();
+        assert!(build(Format::Positions, max.as_bytes(), None).is_err());
+        assert!(build(Format::Positions, max.as_bytes(), Some(5)).is_err());
+        assert!(build(Format::Positions, b"1 9", Some(5)).is_err());
+        assert!(build(Format::Positions, b"", Some(MAX_POSITIONS_LEN + 1)).is_err());
+        let far = MAX_POSITIONS_LEN.to_string();
+        assert!(build(Format::Positions, far.as_bytes(), None).is_err());
+    }
+
+    #[test]
+    fn queries() {
+        // 01101
+        let bv = BitVector::from([false, true, true, false, true].as_slice());
+        let answer = |line: &str| {
+            let mut out = Vec::new();
+            query(&bv, &args(line), &mut "".as_bytes(), &mut out)?;
+            Ok::<_, String>(String::from_utf8(out).unwrap())
+        };
+        assert_eq!(answer("rank0 5 rank1 3"), Ok("2\n2\n".to_string()));
+        assert_eq!(
+            answer("select0 1 select1 2 select1 3"),
+            Ok("3\n4\n5\n".to_string())
+        );
+        assert_eq!(answer("get 0 get 4 count 1 4"), Ok("0\n1\n2\n".to_string()));
+        assert!(answer("rank1 6").is_err());
+        assert!(answer("get 5").is_err());
+        assert!(answer("count 3 2").is_err());
+        assert!(answer("rank1").is_err());
+        assert!(answer("rank2 1").is_err());
+
+        let mut out = Vec::new();
+        let stdin = "# comment\nrank1 5\n\nselect0 0\n";
+        query(&bv, &[], &mut stdin.as_bytes(), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "3\n0\n");
+        let mut out = Vec::new();
+        assert!(query(&bv, &[], &mut "get 1 2\n".as_bytes(), &mut out).is_err());
+    }
+
+    #[test]
+    fn build_stats_query() {
+        let file = temp_file("run.fid");
+        let build = parse_args(&["build", "ascii", "-", &file]).unwrap();
+        let out = run_with(build, "0110 1").unwrap();
+        assert_eq!(out, format!("wrote 5 bits (3 ones) to {file}\n"));
+
+        let stats = run_with(parse_args(&["stats", &file]).unwrap(), "").unwrap();
+        assert!(stats.starts_with("length:       5 bits (3 ones"), "{stats}");
+        let query = parse_args(&["query", &file]).unwrap();
+        assert_eq!(
+            run_with(query, "rank1 5\nget 3\n"),
+            Ok("3\n0\n".to_string())
+        );
+
+        let missing = temp_file("missing.fid");
+        assert!(run_with(parse_args(&["stats", &missing]).unwrap(), "").is_err());
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn stats_empty() {
+        let file = temp_file("empty.fid");
+        let build = parse_args(&["build", "ascii", "-", &file]).unwrap();
+        run_with(build, "").unwrap();
+        let stats = run_with(parse_args(&["stats", &file]).unwrap(), "").unwrap();
+        assert!(stats.starts_with("length:       0 bits"), "{stats}");
+        assert!(!stats.contains("NaN") && !stats.contains("inf"), "{stats}");
+        fs::remove_file(file).unwrap();
+    }
+}
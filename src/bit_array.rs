@@ -1,4 +1,5 @@
 use crate::util::mask_u64;
+use std::ops::Range;
 
 type Block = u64;
 const BLOCK_SIZE: u64 = Block::BITS as u64;
@@ -79,6 +80,11 @@ impl BitArray {
         BitArray { blocks }
     }
 
+    /// Constructs a new [`BitArray`] from packed blocks.
+    pub fn from_blocks(blocks: Vec<Block>) -> Self {
+        BitArray { blocks }
+    }
+
     pub fn with_capacity(capacity: u64) -> Self {
         let block_len = blocks_for_bits(capacity);
         BitArray {
@@ -90,6 +96,11 @@ impl BitArray {
         BitArray::with_capacity(word_size * capacity)
     }
 
+    /// Returns the packed blocks of the array.
+    pub fn as_blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
     /// Returns the number of blocks in the array.
     pub fn block_len(&self) -> usize {
         self.blocks.len()
@@ -236,11 +247,61 @@ impl BitArray {
                 slice
             };
 
-            self.set_slice_unchecked(
-                new_len - tail.len() as u64,
-                tail.len() as u64,
-                pack_block(tail),
-            );
+            if !tail.is_empty() {
+                self.set_slice_unchecked(
+                    new_len - tail.len() as u64,
+                    tail.len() as u64,
+                    pack_block(tail),
+                );
+            }
+        }
+    }
+
+    /// Copies `len` bits at position `src_i` of `src` to position `i`.
+    ///
+    /// # Panics
+    /// * End position of the source exceeds the capacity of `src`.
+    pub fn copy_from(&mut self, i: u64, src: &BitArray, src_i: u64, len: u64) {
+        assert!(src_i + len <= src.len());
+        if len == 0 {
+            return;
+        }
+        self.ensure_len(i + len);
+
+        let mut k = 0;
+        while k < len {
+            let size = (len - k).min(BLOCK_SIZE);
+            let slice = src.get_slice(src_i + k, size);
+            // SAFETY: `ensure_len()` ensures valid len
+            unsafe { self.set_slice_unchecked(i + k, size, slice) }
+            k += size;
+        }
+    }
+
+    /// Copies the bits in `src` to position `dest`, which may overlap.
+    ///
+    /// # Panics
+    /// * End position of `src` exceeds the capacity.
+    pub fn copy_within(&mut self, src: Range<u64>, dest: u64) {
+        assert!(src.end <= self.len());
+        let len = src.end.saturating_sub(src.start);
+        if len == 0 || src.start == dest {
+            return;
+        }
+        self.ensure_len(dest + len);
+
+        // Copy in the direction that reads bits before overwriting them.
+        let chunks = (0..len.div_ceil(BLOCK_SIZE)).map(|k| k * BLOCK_SIZE);
+        let mut copy = |k: u64| {
+            let size = (len - k).min(BLOCK_SIZE);
+            let slice = self.get_slice(src.start + k, size);
+            // SAFETY: `ensure_len()` ensures valid len
+            unsafe { self.set_slice_unchecked(dest + k, size, slice) }
+        };
+        if dest < src.start {
+            chunks.for_each(&mut copy);
+        } else {
+            chunks.rev().for_each(&mut copy);
         }
     }
 
@@ -450,6 +511,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn copy_from() {
+        let mut src = BitArray::new();
+        for i in 0..300 {
+            src.set_bit(i, i % 3 == 0 || i % 7 == 0);
+        }
+        for &(i, src_i, len) in &[(0, 0, 300), (5, 0, 64), (0, 5, 129), (77, 13, 200), (64, 64, 0)] {
+            let mut ba = bit_arr![true; 400];
+            ba.copy_from(i, &src, src_i, len);
+            for j in 0..400 {
+                let expected = if (i..i + len).contains(&j) {
+                    src.get_bit(src_i + j - i)
+                } else {
+                    true
+                };
+                assert_eq!(ba.get_bit(j), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn copy_within() {
+        let mut src = BitArray::new();
+        for i in 0..300 {
+            src.set_bit(i, i % 3 == 0 || i % 7 == 0);
+        }
+        for &(start, end, dest) in &[(0, 300, 0), (0, 200, 5), (5, 205, 0), (13, 290, 77), (77, 290, 13)] {
+            let mut ba = src.clone();
+            ba.copy_within(start..end, dest);
+            for j in 0..ba.len() {
+                let expected = if (dest..dest + end - start).contains(&j) {
+                    src.get_bit(start + j - dest)
+                } else {
+                    j < src.len() && src.get_bit(j)
+                };
+                assert_eq!(ba.get_bit(j), expected);
+            }
+        }
+    }
+
     #[test]
     fn extend_with_resize() {
         let mut ba = bit_arr![false; BLOCK_SIZE * 4];
@@ -485,4 +586,18 @@ mod tests {
             assert_eq!(arr.get_bit(i as u64), slice[i - 1]);
         }
     }
+
+    #[test]
+    fn set_bit_slice_empty_tail() {
+        // The slices end on a block boundary, so nothing is left after the full blocks.
+        for (i, len) in [(0, 0), (0, 64), (0, 128), (3, 61), (3, 125), (64, 0)] {
+            let slice: Vec<bool> = (0..len).map(|j| j % 3 == 0).collect();
+            let mut arr = BitArray::new();
+            arr.set_bit_slice(i, &slice);
+            assert_eq!(arr.len(), (i + len).div_ceil(BLOCK_SIZE) * BLOCK_SIZE);
+            for (j, &b) in slice.iter().enumerate() {
+                assert_eq!(arr.get_bit(i + j as u64), b);
+            }
+        }
+    }
 }
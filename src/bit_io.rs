@@ -0,0 +1,440 @@
+use crate::bit_array::BitArray;
+use crate::util::log2;
+
+const WORD_SIZE: u64 = 64;
+
+/// A variable-length code for integers, see [`BitWriter::write`] and [`BitReader::read`].
+///
+/// Codes are written least significant bit first, so the binary part of a
+/// code can be read back with a single [`BitArray::get_slice`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemDbg, mem_dbg::MemSize))]
+pub enum Code {
+    /// `n` 0s followed by a 1.
+    Unary,
+    /// Elias gamma code of `n + 1`.
+    Gamma,
+    /// Elias delta code of `n + 1`.
+    Delta,
+    /// Golomb-Rice code with parameter `2^k`:
+    /// `n >> k` in unary followed by the low `k` bits of `n`.
+    Rice(u32),
+    /// The low bits of `n` with the given width.
+    Fixed(u32),
+}
+
+impl Code {
+    /// Returns the number of bits used to encode `n`.
+    pub fn len(self, n: u64) -> u64 {
+        match self {
+            Code::Unary => n + 1,
+            Code::Gamma => 2 * log2(n + 1) as u64 + 1,
+            Code::Delta => {
+                let b = log2(n + 1) as u64;
+                Code::Gamma.len(b) + b
+            }
+            Code::Rice(k) => (n >> k) + 1 + k as u64,
+            Code::Fixed(width) => width as u64,
+        }
+    }
+}
+
+/// Writes codes of integers by appending them to a [`BitArray`].
+///
+/// # Examples
+///
+/// ```
+/// # use fid::{BitWriter, Code};
+/// let mut writer = BitWriter::new();
+/// writer.write_gamma(5);
+/// writer.write_rice(100, 4);
+/// writer.write_all(Code::Delta, [0, 1, 1 << 40]);
+///
+/// let mut reader = writer.reader();
+/// assert_eq!(reader.read_gamma(), 5);
+/// assert_eq!(reader.read_rice(4), 100);
+/// let mut deltas = [0; 3];
+/// reader.read_into(Code::Delta, &mut deltas);
+/// assert_eq!(deltas, [0, 1, 1 << 40]);
+/// assert!(reader.is_empty());
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemDbg, mem_dbg::MemSize))]
+pub struct BitWriter {
+    bits: BitArray,
+    /// Number of bits written.
+    len: u64,
+}
+
+impl BitWriter {
+    /// Constructs a new, empty [`BitWriter`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs a new, empty [`BitWriter`] with capacity for at least `capacity` bits.
+    pub fn with_capacity(capacity: u64) -> Self {
+        BitWriter {
+            bits: BitArray::with_capacity(capacity),
+            len: 0,
+        }
+    }
+
+    /// Constructs a [`BitWriter`] appending to the first `len` bits of `bits`.
+    ///
+    /// # Panics
+    /// * `len` exceeds the length of `bits`.
+    pub fn from_bit_array(mut bits: BitArray, len: u64) -> Self {
+        assert!(len <= bits.len());
+        bits.truncate(len);
+        bits.set_slice(len, bits.len() - len, 0);
+        BitWriter { bits, len }
+    }
+
+    /// Returns the number of bits written.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if no bits have been written.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the written bits. Bits past [`len`] are 0.
+    ///
+    /// [`len`]: BitWriter::len
+    pub fn as_bit_array(&self) -> &BitArray {
+        &self.bits
+    }
+
+    /// Returns the written bits, see [`as_bit_array`].
+    ///
+    /// [`as_bit_array`]: BitWriter::as_bit_array
+    pub fn into_bit_array(self) -> BitArray {
+        self.bits
+    }
+
+    /// Returns a reader over the written bits.
+    pub fn reader(&self) -> BitReader<'_> {
+        BitReader::with_len(&self.bits, self.len)
+    }
+
+    /// Writes the low `width` bits of `n`.
+    ///
+    /// # Panics
+    /// * `width` is greater than 64.
+    pub fn write_fixed(&mut self, n: u64, width: u64) {
+        assert!(width <= WORD_SIZE);
+        self.bits.set_slice(self.len, width, n);
+        self.len += width;
+    }
+
+    /// Writes `n` 0s followed by a 1.
+    pub fn write_unary(&mut self, n: u64) {
+        let mut zeros = n;
+        while zeros >= WORD_SIZE {
+            self.write_fixed(0, WORD_SIZE);
+            zeros -= WORD_SIZE;
+        }
+        self.write_fixed(1 << zeros, zeros + 1);
+    }
+
+    /// Writes the Elias gamma code of `n + 1`, so that 0 can be encoded.
+    ///
+    /// # Panics
+    /// * `n` is `u64::MAX`.
+    pub fn write_gamma(&mut self, n: u64) {
+        let x = n.checked_add(1).expect("cannot encode u64::MAX");
+        let b = log2(x) as u64;
+        self.write_unary(b);
+        self.write_fixed(x, b);
+    }
+
+    /// Writes the Elias delta code of `n + 1`, so that 0 can be encoded.
+    ///
+    /// # Panics
+    /// * `n` is `u64::MAX`.
+    pub fn write_delta(&mut self, n: u64) {
+        let x = n.checked_add(1).expect("cannot encode u64::MAX");
+        let b = log2(x) as u64;
+        self.write_gamma(b);
+        self.write_fixed(x, b);
+    }
+
+    /// Writes the Golomb-Rice code of `n` with parameter `2^k`.
+    ///
+    /// # Panics
+    /// * `k` is greater than 63.
+    pub fn write_rice(&mut self, n: u64, k: u32) {
+        assert!((k as u64) < WORD_SIZE);
+        self.write_unary(n >> k);
+        self.write_fixed(n, k as u64);
+    }
+
+    /// Writes `n` with `code`.
+    pub fn write(&mut self, code: Code, n: u64) {
+        match code {
+            Code::Unary => self.write_unary(n),
+            Code::Gamma => self.write_gamma(n),
+            Code::Delta => self.write_delta(n),
+            Code::Rice(k) => self.write_rice(n, k),
+            Code::Fixed(width) => self.write_fixed(n, width as u64),
+        }
+    }
+
+    /// Writes every integer of `iter` with `code`.
+    pub fn write_all<I: IntoIterator<Item = u64>>(&mut self, code: Code, iter: I) {
+        for n in iter {
+            self.write(code, n);
+        }
+    }
+}
+
+/// Reads codes of integers with a cursor over a [`BitArray`].
+///
+/// Reading a code that extends past the end panics.
+/// See [`BitWriter`] for an example.
+#[derive(Debug, Clone, Copy)]
+pub struct BitReader<'a> {
+    bits: &'a BitArray,
+    pos: u64,
+    len: u64,
+}
+
+impl<'a> BitReader<'a> {
+    /// Constructs a new [`BitReader`] over all bits of `bits`.
+    pub fn new(bits: &'a BitArray) -> Self {
+        Self::with_len(bits, bits.len())
+    }
+
+    /// Constructs a new [`BitReader`] over the first `len` bits of `bits`.
+    ///
+    /// # Panics
+    /// * `len` exceeds the length of `bits`.
+    pub fn with_len(bits: &'a BitArray, len: u64) -> Self {
+        assert!(len <= bits.len());
+        BitReader { bits, pos: 0, len }
+    }
+
+    /// Returns true if no bits remain after the cursor.
+    pub fn is_empty(&self) -> bool {
+        self.pos == self.len
+    }
+
+    /// Returns the position of the cursor.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Moves the cursor to `pos`.
+    ///
+    /// # Panics
+    /// * `pos` exceeds the number of bits.
+    pub fn set_position(&mut self, pos: u64) {
+        assert!(pos <= self.len);
+        self.pos = pos;
+    }
+
+    /// Returns the number of bits after the cursor.
+    pub fn remaining(&self) -> u64 {
+        self.len - self.pos
+    }
+
+    /// Reads `width` bits.
+    ///
+    /// # Panics
+    /// * `width` is greater than 64.
+    pub fn read_fixed(&mut self, width: u64) -> u64 {
+        assert!(width <= WORD_SIZE);
+        assert!(width <= self.remaining(), "unexpected end of bits");
+        let n = self.bits.get_slice(self.pos, width);
+        self.pos += width;
+        n
+    }
+
+    /// Reads a unary code, counting the 0s before the next 1.
+    pub fn read_unary(&mut self) -> u64 {
+        let mut n = 0;
+        loop {
+            let size = self.remaining().min(WORD_SIZE);
+            assert!(size > 0, "unexpected end of bits");
+            let word = self.bits.get_slice(self.pos, size);
+            if word != 0 {
+                let zeros = word.trailing_zeros() as u64;
+                self.pos += zeros + 1;
+                return n + zeros;
+            }
+            self.pos += size;
+            n += size;
+        }
+    }
+
+    /// Reads an Elias gamma code, see [`BitWriter::write_gamma`].
+    pub fn read_gamma(&mut self) -> u64 {
+        let b = self.read_unary();
+        assert!(b < WORD_SIZE, "invalid gamma code");
+        ((1 << b) | self.read_fixed(b)) - 1
+    }
+
+    /// Reads an Elias delta code, see [`BitWriter::write_delta`].
+    pub fn read_delta(&mut self) -> u64 {
+        let b = self.read_gamma();
+        assert!(b < WORD_SIZE, "invalid delta code");
+        ((1 << b) | self.read_fixed(b)) - 1
+    }
+
+    /// Reads a Golomb-Rice code with parameter `2^k`.
+    ///
+    /// # Panics
+    /// * `k` is greater than 63.
+    pub fn read_rice(&mut self, k: u32) -> u64 {
+        assert!((k as u64) < WORD_SIZE);
+        let q = self.read_unary();
+        (q << k) | self.read_fixed(k as u64)
+    }
+
+    /// Reads an integer with `code`.
+    pub fn read(&mut self, code: Code) -> u64 {
+        match code {
+            Code::Unary => self.read_unary(),
+            Code::Gamma => self.read_gamma(),
+            Code::Delta => self.read_delta(),
+            Code::Rice(k) => self.read_rice(k),
+            Code::Fixed(width) => self.read_fixed(width as u64),
+        }
+    }
+
+    /// Fills `out` with integers read with `code`.
+    pub fn read_into(&mut self, code: Code, out: &mut [u64]) {
+        match code {
+            // Fixed-width codes are read without the dispatch of `read`.
+            Code::Fixed(width) => {
+                let width = width as u64;
+                assert!(width <= WORD_SIZE);
+                assert!(
+                    width * out.len() as u64 <= self.remaining(),
+                    "unexpected end of bits"
+                );
+                for n in out.iter_mut() {
+                    *n = self.bits.get_slice(self.pos, width);
+                    self.pos += width;
+                }
+            }
+            _ => out.iter_mut().for_each(|n| *n = self.read(code)),
+        }
+    }
+
+    /// Reads `count` integers with `code`.
+    pub fn read_vec(&mut self, code: Code, count: usize) -> Vec<u64> {
+        let mut out = vec![0; count];
+        self.read_into(code, &mut out);
+        out
+    }
+
+    /// Returns an iterator reading integers with `code` until the end.
+    ///
+    /// The iterator never ends for `Code::Fixed(0)`, which does not move the cursor.
+    pub fn iter(self, code: Code) -> impl Iterator<Item = u64> + 'a {
+        let mut reader = self;
+        std::iter::from_fn(move || (!reader.is_empty()).then(|| reader.read(code)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::mask_u64;
+    use rand::{Rng, SeedableRng, StdRng};
+
+    const CODES: &[Code] = &[
+        Code::Unary,
+        Code::Gamma,
+        Code::Delta,
+        Code::Rice(0),
+        Code::Rice(5),
+        Code::Rice(63),
+        Code::Fixed(0),
+        Code::Fixed(13),
+        Code::Fixed(64),
+    ];
+
+    fn gen_value(rng: &mut StdRng, code: Code) -> u64 {
+        match code {
+            Code::Unary => rng.gen_range(0, 300),
+            Code::Rice(k) => (rng.gen_range(0, 200) << k) | (rng.gen::<u64>() & mask_u64(k as u64)),
+            Code::Fixed(width) => rng.gen::<u64>() & mask_u64(width as u64),
+            _ => rng.gen::<u64>() >> rng.gen_range(1, 64),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        let mut writer = BitWriter::new();
+        let mut expected = Vec::new();
+        for _ in 0..10_000 {
+            let code = CODES[rng.gen_range(0, CODES.len())];
+            let n = gen_value(&mut rng, code);
+            let len = writer.len();
+            writer.write(code, n);
+            assert_eq!(writer.len() - len, code.len(n), "{code:?} {n}");
+            expected.push((code, n));
+        }
+        for n in [0, 1, u64::MAX - 1] {
+            writer.write_gamma(n);
+            writer.write_delta(n);
+            expected.push((Code::Gamma, n));
+            expected.push((Code::Delta, n));
+        }
+
+        let mut reader = writer.reader();
+        for &(code, n) in &expected {
+            assert_eq!(reader.read(code), n, "{code:?}");
+        }
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn bulk() {
+        for &code in CODES {
+            let values: Vec<u64> = match code {
+                Code::Fixed(width) => (0..500).map(|n| n & mask_u64(width as u64)).collect(),
+                _ => (0..500).collect(),
+            };
+            let mut writer = BitWriter::new();
+            writer.write_all(code, values.iter().copied());
+
+            let mut reader = writer.reader();
+            assert_eq!(reader.read_vec(code, 100), values[..100]);
+            if code != Code::Fixed(0) {
+                assert!(reader.iter(code).eq(values[100..].iter().copied()));
+            }
+        }
+    }
+
+    #[test]
+    fn append_to_bit_array() {
+        let mut ba = BitArray::from_bit(true, 100);
+        ba.set_bit(99, false);
+        let mut writer = BitWriter::from_bit_array(ba, 70);
+        writer.write_unary(200);
+        assert_eq!(writer.len(), 271);
+
+        let mut reader = writer.reader();
+        assert_eq!(reader.read_fixed(64), !0);
+        reader.set_position(70);
+        assert_eq!(reader.read_unary(), 200);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn read_past_end() {
+        let mut writer = BitWriter::new();
+        writer.write_fixed(0, 10);
+        writer.reader().read_unary();
+    }
+}
@@ -1,17 +1,22 @@
 use crate::bit_array::*;
 use crate::coding::*;
+use crate::complement::Complement;
 use crate::fid::FID;
 use crate::fid_iter::FidBitIter;
+use crate::space_report::SpaceReport;
 use crate::util::{mask_u64, phi_sub};
+use std::iter::FusedIterator;
 use std::num::{NonZeroU32, NonZeroU8};
-use std::ops::Index;
+use std::ops::{Index, Not, Range};
 
 use roxygen::*;
 
-const SBLOCK_SIZE: u64 = 7; // ceil(log(SBLOCK_SIZE + 1))
-const LBLOCK_WIDTH: u64 = 1024;
+pub(crate) const SBLOCK_SIZE: u64 = 7; // ceil(log(SBLOCK_SIZE + 1))
+pub(crate) const LBLOCK_WIDTH: u64 = 1024;
 const LBLOCK_SIZE: u64 = 10;
-const SELECT_UNIT_NUM: u64 = 4096;
+pub(crate) const SELECT_UNIT_NUM: u64 = 4096;
+/// Bits of the fixed-size fields: `len`, `ones`, `last_sblock_bits` and `pointer`.
+const HEADER_SIZE: u64 = 4 * u64::BITS as u64;
 
 #[macro_export]
 macro_rules! bit_vec {
@@ -94,6 +99,18 @@ impl BitVector {
         FidBitIter::new(&self)
     }
 
+    /// Returns an iterator over the bits packed into 64-bit words.
+    ///
+    /// Unlike [`FID::iter_words`], small blocks are decoded in order
+    /// without searching for the index pointer of each block.
+    pub fn iter_words(&self) -> BitVectorWordIter<'_> {
+        BitVectorWordIter {
+            bv: self,
+            i: 0,
+            pointer: 0,
+        }
+    }
+
     pub fn to_vec(&self) -> Vec<bool> {
         self.iter().collect()
     }
@@ -196,6 +213,255 @@ impl BitVector {
         }
     }
 
+    /// Appends `size` bits from `slice` at the end of the vector.
+    ///
+    /// # Panics
+    /// * `size` is greater than 64.
+    pub fn push_slice(&mut self, slice: u64, size: u64) {
+        debug_assert!(size <= 64);
+
+        let slice = slice & mask_u64(size);
+        let lo_size = size.min(SBLOCK_WIDTH - self.len % SBLOCK_WIDTH);
+        self.push_sblock_slice(slice & mask_u64(lo_size), lo_size);
+        if lo_size < size {
+            self.push_sblock_slice(slice >> lo_size, size - lo_size);
+        }
+    }
+
+    /// Appends `size` bits which fit in the last small block.
+    fn push_sblock_slice(&mut self, bits: u64, size: u64) {
+        if size == 0 {
+            return;
+        }
+        let ones = bits.count_ones() as u64;
+        let zeros = size - ones;
+
+        // Bits within a small block are in the same large block,
+        // and at most one select unit can be crossed.
+        if self.ones % SELECT_UNIT_NUM + ones >= SELECT_UNIT_NUM {
+            self.push_select_unit(true);
+        }
+        if (self.len - self.ones) % SELECT_UNIT_NUM + zeros >= SELECT_UNIT_NUM {
+            self.push_select_unit(false);
+        }
+
+        self.last_sblock_bits |= bits << (self.len % SBLOCK_WIDTH);
+        self.ones += ones;
+        self.len += size;
+
+        if self.len.is_multiple_of(SBLOCK_WIDTH) {
+            self.push_blocks();
+        }
+    }
+
+    /// Constructs a new [`BitVector`] with the first `len` bits of `words`.
+    ///
+    /// # Panics
+    /// * `len` exceeds the number of bits in `words`.
+    pub fn from_words(words: &[u64], len: u64) -> Self {
+        assert!(len <= words.len() as u64 * SBLOCK_WIDTH);
+
+        let mut vec = Self::with_capacity(len);
+        for (k, &word) in words.iter().enumerate() {
+            let size = len.saturating_sub(k as u64 * SBLOCK_WIDTH).min(SBLOCK_WIDTH);
+            if size == 0 {
+                break;
+            }
+            vec.push_slice(word, size);
+        }
+        vec
+    }
+
+    /// Constructs a new [`BitVector`] with the first `len` bits of `words`,
+    /// using all available threads.
+    ///
+    /// The words are split into chunks aligned to large blocks, which are
+    /// encoded concurrently and then stitched together.
+    ///
+    /// # Panics
+    /// * `len` exceeds the number of bits in `words`.
+    pub fn from_words_parallel(words: &[u64], len: u64) -> Self {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self::from_words_in_parts(words, len, threads)
+    }
+
+    pub(crate) fn from_words_in_parts(words: &[u64], len: u64, parts: usize) -> Self {
+        assert!(len <= words.len() as u64 * SBLOCK_WIDTH);
+
+        let lblock_len = len.div_ceil(LBLOCK_WIDTH);
+        let part_width = lblock_len.div_ceil(parts.max(1) as u64).max(1) * LBLOCK_WIDTH;
+        if part_width >= len {
+            return Self::from_words(words, len);
+        }
+
+        let part_words = (part_width / SBLOCK_WIDTH) as usize;
+        let words = &words[..len.div_ceil(SBLOCK_WIDTH) as usize];
+        let mut parts = std::thread::scope(|s| {
+            let handles: Vec<_> = words
+                .chunks(part_words)
+                .enumerate()
+                .map(|(k, chunk)| {
+                    let part_len = (len - k as u64 * part_width).min(part_width);
+                    s.spawn(move || Self::from_words(chunk, part_len))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+                .into_iter()
+        });
+
+        let mut vec = parts.next().unwrap_or_default();
+        for part in parts {
+            vec.append(&part);
+        }
+        vec
+    }
+
+    /// Appends all bits of `other` at the end of the vector.
+    ///
+    /// When the vector ends at a small block, encoded small blocks are copied
+    /// verbatim instead of being decoded. Otherwise the bits are re-encoded word by word.
+    pub fn append(&mut self, other: &BitVector) {
+        if self.len.is_multiple_of(SBLOCK_WIDTH) {
+            self.extend_from_sblock(other, 0);
+        } else {
+            self.extend_from_bits(other, 0);
+        }
+    }
+
+    /// Constructs a new [`BitVector`] by appending all vectors in order.
+    ///
+    /// See [`append`] for when encoded blocks can be copied.
+    ///
+    /// [`append`]: BitVector::append
+    pub fn concat<'a, I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = &'a BitVector>,
+    {
+        let mut vec = Self::new();
+        for other in iter {
+            vec.append(other);
+        }
+        vec
+    }
+
+    /// Splits the vector in two at position `at`.
+    ///
+    /// Returns a new vector with the bits `[at, len)`, leaving `[0, at)` in `self`.
+    /// When `at` is a multiple of `SBLOCK_WIDTH`, encoded small blocks are copied
+    /// verbatim instead of being decoded.
+    ///
+    /// # Panics
+    /// * `at` is greater than the length.
+    pub fn split_off(&mut self, at: u64) -> BitVector {
+        assert!(at <= self.len);
+
+        let mut other = BitVector::new();
+        if at.is_multiple_of(SBLOCK_WIDTH) {
+            other.extend_from_sblock(self, at / SBLOCK_WIDTH);
+        } else {
+            other.extend_from_bits(self, at);
+        }
+        self.truncate(at);
+        other
+    }
+
+    /// Appends the bits of `other` from the small block at `sblock_pos` onwards
+    /// when `self` ends at a small block, copying the encoded small blocks.
+    fn extend_from_sblock(&mut self, other: &BitVector, sblock_pos: u64) {
+        debug_assert!(self.len.is_multiple_of(SBLOCK_WIDTH));
+
+        let start = sblock_pos * SBLOCK_WIDTH;
+        let sblock_end_pos = other.len / SBLOCK_WIDTH;
+        let (pointer, ones) = other.get_pointer_and_rank(&TABLE, start);
+        let lblock_pos = self.lblocks.len();
+
+        self.sblocks.copy_from(
+            self.len / SBLOCK_WIDTH * SBLOCK_SIZE,
+            &other.sblocks,
+            sblock_pos * SBLOCK_SIZE,
+            (sblock_end_pos - sblock_pos) * SBLOCK_SIZE,
+        );
+        self.indices
+            .copy_from(self.pointer, &other.indices, pointer, other.pointer - pointer);
+
+        if self.len.is_multiple_of(LBLOCK_WIDTH) && start.is_multiple_of(LBLOCK_WIDTH) {
+            // Large blocks line up, so their samples only need an offset.
+            let other_lblock_pos = (start / LBLOCK_WIDTH) as usize;
+            let (self_ones, self_pointer) = (self.ones, self.pointer);
+            let lblocks = &other.lblocks[other_lblock_pos..];
+            let pointers = &other.pointers[other_lblock_pos..];
+            self.lblocks.extend(lblocks.iter().map(|r| r - ones + self_ones));
+            self.pointers.extend(pointers.iter().map(|p| p - pointer + self_pointer));
+
+            let last_sblock_ones = other.last_sblock_bits.count_ones() as u64;
+            self.len += sblock_end_pos * SBLOCK_WIDTH - start;
+            self.ones += other.ones - last_sblock_ones - ones;
+            self.pointer += other.pointer - pointer;
+        } else {
+            for j in sblock_pos..sblock_end_pos {
+                let k = other.sblocks.get_word(j, SBLOCK_SIZE);
+                self.len += SBLOCK_WIDTH;
+                self.ones += k;
+                self.pointer += TABLE.get_code_size(k as u32);
+                if self.len.is_multiple_of(LBLOCK_WIDTH) {
+                    self.lblocks.push(self.ones);
+                    self.pointers.push(self.pointer);
+                }
+            }
+        }
+
+        self.push_sblock_slice(other.last_sblock_bits, other.len % SBLOCK_WIDTH);
+        self.rebuild_select_units(lblock_pos);
+    }
+
+    /// Appends the bits of `other` from position `i` onwards by re-encoding them.
+    fn extend_from_bits(&mut self, other: &BitVector, mut i: u64) {
+        while i < other.len {
+            let size = (other.len - i).min(SBLOCK_WIDTH);
+            self.push_slice(other.get_slice(i, size), size);
+            i += size;
+        }
+    }
+
+    /// Recomputes the select samples from the large block at `lblock_pos` onwards.
+    fn rebuild_select_units(&mut self, lblock_pos: usize) {
+        for b in [false, true] {
+            let mut units = std::mem::take(if b {
+                &mut self.select1_unit_pointers
+            } else {
+                &mut self.select0_unit_pointers
+            });
+
+            // Number of `b` bits before the large block at `pos`.
+            let count = |pos: usize| {
+                if pos > self.lblocks.len() {
+                    phi_sub(b, self.len, self.ones)
+                } else {
+                    phi_sub(b, LBLOCK_WIDTH * pos as u64, self.get_lblock(pos))
+                }
+            };
+
+            units.truncate((count(lblock_pos) / SELECT_UNIT_NUM) as usize);
+            let mut next = (units.len() as u64 + 1) * SELECT_UNIT_NUM;
+            for pos in lblock_pos..=self.lblocks.len() {
+                let end = count(pos + 1);
+                while next <= end {
+                    units.push(pos);
+                    next += SELECT_UNIT_NUM;
+                }
+            }
+
+            if b {
+                self.select1_unit_pointers = units;
+            } else {
+                self.select0_unit_pointers = units;
+            }
+        }
+    }
+
     #[cold]
     fn push_select_unit(&mut self, b: bool) {
         let vec = if b {
@@ -224,6 +490,181 @@ impl BitVector {
         }
     }
 
+    /// Sets the bit at position `i` to `b`.
+    ///
+    /// Small blocks are re-encoded in place, and the ranks of all following large
+    /// blocks are updated, which takes time linear in the number of large blocks
+    /// after `i`. When the size of the index changes, all following indices are also
+    /// moved, which takes time linear in the number of bits after `i`. Select samples
+    /// are only rebuilt from the first large block whose rank crosses a sample.
+    ///
+    /// # Panics
+    /// * `i` is out of bounds.
+    pub fn set(&mut self, i: u64, b: bool) {
+        assert!(i < self.len);
+
+        let mask = 1 << (i % SBLOCK_WIDTH);
+        let last_sblock_width = self.len % SBLOCK_WIDTH;
+        let packed_len = self.len - last_sblock_width;
+        if i >= packed_len {
+            if (self.last_sblock_bits & mask != 0) != b {
+                let bits = self.last_sblock_bits ^ mask;
+                self.truncate_sblocks(packed_len / SBLOCK_WIDTH);
+                self.push_sblock_slice(bits, last_sblock_width);
+            }
+            return;
+        }
+
+        let table = TABLE.as_ref();
+        let sblock_pos = i / SBLOCK_WIDTH;
+        let lblock_pos = (i / LBLOCK_WIDTH) as usize;
+        let sblock = self.sblocks.get_word(sblock_pos, SBLOCK_SIZE);
+        let pointer = self.get_pointer_and_rank(table, i).0;
+        let bits = self
+            .read_index(table, sblock as u8, pointer)
+            .decode(table, NonZeroU32::new(SBLOCK_WIDTH as u32).unwrap());
+        if (bits & mask != 0) == b {
+            return;
+        }
+
+        let bits = bits ^ mask;
+        let new_sblock = bits.count_ones();
+        let (index, index_size) = table.encode(bits, new_sblock);
+        let old_index_size = table.get_code_size(sblock as u32);
+        if index_size != old_index_size {
+            let rest = pointer + old_index_size..self.pointer;
+            self.indices.copy_within(rest, pointer + index_size);
+            self.pointer = self.pointer + index_size - old_index_size;
+            truncate_bits(&mut self.indices, self.pointer);
+            for p in &mut self.pointers[lblock_pos..] {
+                *p = *p + index_size - old_index_size;
+            }
+        }
+        self.indices.set_slice(pointer, index_size, index);
+        self.sblocks
+            .set_word(sblock_pos, SBLOCK_SIZE, new_sblock as u64);
+
+        // The samples only change if a count of 1s or 0s before a large block
+        // (or in total) crosses a multiple of `SELECT_UNIT_NUM`.
+        let crosses = |width: u64, ones: u64| {
+            let (inc, dec) = if b {
+                (ones, width - ones)
+            } else {
+                (width - ones, ones)
+            };
+            inc.is_multiple_of(SELECT_UNIT_NUM) || (dec + 1).is_multiple_of(SELECT_UNIT_NUM)
+        };
+        let mut rebuild_pos = None;
+        for (j, rank) in self.lblocks.iter_mut().enumerate().skip(lblock_pos) {
+            *rank = if b { *rank + 1 } else { *rank - 1 };
+            if rebuild_pos.is_none() && crosses(LBLOCK_WIDTH * (j as u64 + 1), *rank) {
+                rebuild_pos = Some(j);
+            }
+        }
+        self.ones = if b { self.ones + 1 } else { self.ones - 1 };
+        if rebuild_pos.is_none() && crosses(self.len, self.ones) {
+            rebuild_pos = Some(self.lblocks.len());
+        }
+        if let Some(pos) = rebuild_pos {
+            self.rebuild_select_units(pos);
+        }
+    }
+
+    /// Flips the bit at position `i`.
+    ///
+    /// See [`set`] for the cost of modifying a bit.
+    ///
+    /// [`set`]: BitVector::set
+    ///
+    /// # Panics
+    /// * `i` is out of bounds.
+    pub fn flip(&mut self, i: u64) {
+        let b = self.get(i);
+        self.set(i, !b);
+    }
+
+    /// Removes the last bit and returns it, or [`None`] if the vector is empty.
+    ///
+    /// Takes the same time as [`rank`](FID::rank) on the last bit.
+    pub fn pop(&mut self) -> Option<bool> {
+        let len = self.len.checked_sub(1)?;
+        let b = self.get(len);
+        self.truncate(len);
+        Some(b)
+    }
+
+    /// Shortens the vector, keeping the first `len` bits and dropping the rest.
+    ///
+    /// Has no effect if `len` is greater or equal to the current length.
+    /// Only the small block at `len` is decoded and re-appended,
+    /// so this takes the same time as [`rank`](FID::rank) on `len`.
+    pub fn truncate(&mut self, len: u64) {
+        if len >= self.len {
+            return;
+        }
+
+        let size = len % SBLOCK_WIDTH;
+        let packed_len = self.len - self.len % SBLOCK_WIDTH;
+        let bits = if len >= packed_len {
+            self.last_sblock_bits
+        } else if size != 0 {
+            self.decode_sblock(&TABLE, len - size, NonZeroU32::new(size as u32).unwrap())
+        } else {
+            0
+        };
+        self.truncate_sblocks(len / SBLOCK_WIDTH);
+        self.push_sblock_slice(bits & mask_u64(size), size);
+    }
+
+    /// Inverts every bit of the vector in place.
+    ///
+    /// Each small block of class `k` is re-encoded as class `SBLOCK_WIDTH - k` by
+    /// mirroring its enumerative code, which keeps the size of every index.
+    /// No bits are decoded.
+    pub fn invert(&mut self) {
+        let table = TABLE.as_ref();
+        let mut pointer = 0;
+        for j in 0..self.len / SBLOCK_WIDTH {
+            let k = self.sblocks.get_word(j, SBLOCK_SIZE);
+            let code_size = table.get_code_size(k as u32);
+            let code = self.indices.get_slice(pointer, code_size);
+            let code = table.invert_code(code, k as u32);
+            self.indices.set_slice(pointer, code_size, code);
+            self.sblocks.set_word(j, SBLOCK_SIZE, SBLOCK_WIDTH - k);
+            pointer += code_size;
+        }
+        for (pos, rank) in self.lblocks.iter_mut().enumerate() {
+            *rank = LBLOCK_WIDTH * (pos as u64 + 1) - *rank;
+        }
+        std::mem::swap(
+            &mut self.select0_unit_pointers,
+            &mut self.select1_unit_pointers,
+        );
+        self.ones = self.len - self.ones;
+        self.last_sblock_bits = !self.last_sblock_bits & mask_u64(self.len % SBLOCK_WIDTH);
+    }
+
+    /// Drops all bits from the small block at `sblock_pos` onwards.
+    fn truncate_sblocks(&mut self, sblock_pos: u64) {
+        let len = sblock_pos * SBLOCK_WIDTH;
+        debug_assert!(len <= self.len);
+
+        let (pointer, ones) = self.get_pointer_and_rank(&TABLE, len);
+        truncate_bits(&mut self.sblocks, sblock_pos * SBLOCK_SIZE);
+        truncate_bits(&mut self.indices, pointer);
+        self.lblocks.truncate((len / LBLOCK_WIDTH) as usize);
+        self.pointers.truncate((len / LBLOCK_WIDTH) as usize);
+        self.select1_unit_pointers
+            .truncate((ones / SELECT_UNIT_NUM) as usize);
+        self.select0_unit_pointers
+            .truncate(((len - ones) / SELECT_UNIT_NUM) as usize);
+
+        self.len = len;
+        self.ones = ones;
+        self.pointer = pointer;
+        self.last_sblock_bits = 0;
+    }
+
     pub fn shrink_to_fit(&mut self) {
         self.sblocks.shrink_to_fit();
         self.lblocks.shrink_to_fit();
@@ -233,6 +674,79 @@ impl BitVector {
         self.select0_unit_pointers.shrink_to_fit();
     }
 
+    /// Returns the number of bits used by the encoded blocks and samples,
+    /// excluding unused capacity.
+    #[cfg(test)]
+    pub(crate) fn encoded_bits(&self) -> u64 {
+        let words = self.lblocks.len() + self.pointers.len();
+        let units = self.select1_unit_pointers.len() + self.select0_unit_pointers.len();
+        self.sblocks.len()
+            + self.indices.len()
+            + words as u64 * u64::BITS as u64
+            + units as u64 * usize::BITS as u64
+            + HEADER_SIZE
+    }
+
+    /// Returns the number of bits taken by a shrunk vector with the first `len` bits
+    /// of `words`, computed from the classes of the small blocks without encoding them.
+    pub(crate) fn estimate_encoded_bits(words: &[u64], len: u64) -> u64 {
+        let table = TABLE.as_ref();
+        let mut ones = 0;
+        let mut indices = 0;
+        for (k, &word) in words.iter().enumerate() {
+            let size = len.saturating_sub(k as u64 * SBLOCK_WIDTH).min(SBLOCK_WIDTH);
+            let class = (word & mask_u64(size)).count_ones();
+            ones += class as u64;
+            if size == SBLOCK_WIDTH {
+                indices += table.get_code_size(class);
+            }
+        }
+
+        let sblocks = len / SBLOCK_WIDTH * SBLOCK_SIZE;
+        let lblocks = len / LBLOCK_WIDTH;
+        let units = ones / SELECT_UNIT_NUM + (len - ones) / SELECT_UNIT_NUM;
+        sblocks.next_multiple_of(u64::BITS as u64)
+            + indices.next_multiple_of(u64::BITS as u64)
+            + 2 * lblocks * u64::BITS as u64
+            + units * usize::BITS as u64
+            + HEADER_SIZE
+    }
+
+    /// Returns a breakdown of the space used by each component,
+    /// along with the entropy of the bits and a histogram of small block classes.
+    ///
+    /// Takes time linear in the number of small blocks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use fid::BitVector;
+    /// let bv: BitVector = (0..100_000).map(|i| i % 100 == 0).collect();
+    /// let report = bv.space_report();
+    /// assert_eq!(report.ones, 1000);
+    /// assert!(report.bits_per_bit() < 0.5);
+    /// println!("{report}");
+    /// ```
+    pub fn space_report(&self) -> SpaceReport {
+        let mut class_histogram = vec![0; SBLOCK_WIDTH as usize + 1];
+        for j in 0..self.len / SBLOCK_WIDTH {
+            class_histogram[self.sblocks.get_word(j, SBLOCK_SIZE) as usize] += 1;
+        }
+
+        let units = self.select1_unit_pointers.len() + self.select0_unit_pointers.len();
+        SpaceReport {
+            len: self.len,
+            ones: self.ones,
+            sblocks: self.len / SBLOCK_WIDTH * SBLOCK_SIZE,
+            indices: self.pointer,
+            lblocks: self.lblocks.len() as u64 * u64::BITS as u64,
+            pointers: self.pointers.len() as u64 * u64::BITS as u64,
+            select_units: units as u64 * usize::BITS as u64,
+            header: HEADER_SIZE,
+            class_histogram,
+        }
+    }
+
     fn get_unit(&self, b: bool, r: u64) -> usize {
         let vec = if b {
             &self.select1_unit_pointers
@@ -271,8 +785,17 @@ impl BitVector {
         let sblock_end_pos = i / SBLOCK_WIDTH;
         let sblock = self.sblocks.get_word(sblock_end_pos, SBLOCK_SIZE) as u8;
 
-        if let Some(sblock) = NonZeroU8::new(sblock) {
+        if sblock != 0 {
             let pointer = self.get_pointer_and_rank(table, i).0;
+            self.read_index(table, sblock, pointer)
+        } else {
+            EncodedIndex::Zero
+        }
+    }
+
+    /// Read the index of a small block with class `sblock` stored at `pointer`.
+    fn read_index(&self, table: &ComboTable, sblock: u8, pointer: u64) -> EncodedIndex {
+        if let Some(sblock) = NonZeroU8::new(sblock) {
             let code_size = table.get_code_size(sblock.get().into());
             let index = self.indices.get_slice(pointer, code_size);
 
@@ -289,15 +812,53 @@ impl BitVector {
     /// Decode bits up to end of slice (`sblock[0..(i % SBLOCK_WIDTH + size)]`).
     /// Returns whole block when not packed.
     fn decode_sblock(&self, table: &ComboTable, i: u64, size: NonZeroU32) -> u64 {
-        match self.get_index(table, i) {
-            EncodedIndex::Zero => 0,
-            EncodedIndex::Raw { bits } => bits,
-            EncodedIndex::Packed { index, sblock } => {
-                let sblock = sblock.get().into();
-                let end = size.saturating_add((i % SBLOCK_WIDTH) as u32);
-                table.decode_index(index, sblock, end.get().into())
-            }
+        let end = size.saturating_add((i % SBLOCK_WIDTH) as u32);
+        self.get_index(table, i).decode(table, end)
+    }
+
+    /// Returns the number of 1s in the first `p` bits of the small block at `sblock_pos`,
+    /// whose index is stored at `pointer`.
+    fn sblock_rank1(&self, table: &ComboTable, sblock_pos: u64, pointer: u64, p: u64) -> u64 {
+        if p == 0 {
+            return 0;
+        }
+        if sblock_pos >= self.len / SBLOCK_WIDTH {
+            return (self.last_sblock_bits & mask_u64(p)).count_ones() as u64;
         }
+        let sblock = self.sblocks.get_word(sblock_pos, SBLOCK_SIZE) as u32;
+        let code_size = table.get_code_size(sblock);
+        let index = self.indices.get_slice(pointer, code_size);
+        table.decode_rank1(index, sblock, p as u32) as u64
+    }
+
+    /// Returns true if all bits in `range` are `b`.
+    ///
+    /// Short ranges are checked with the classes of their small blocks,
+    /// decoding at most the two partial blocks at the ends.
+    fn is_uniform(&self, b: bool, range: Range<u64>) -> bool {
+        let end = range.end.min(self.len);
+        let start = range.start.min(end);
+        if end - start > LBLOCK_WIDTH {
+            return phi_sub(b, end - start, self.count_ones(start..end)) == end - start;
+        }
+
+        let is_uniform_slice = |i: u64, size: u64| {
+            let bits = self.get_slice(i, size);
+            bits == if b { mask_u64(size) } else { 0 }
+        };
+
+        let sblock_start_pos = start.div_ceil(SBLOCK_WIDTH);
+        let sblock_end_pos = end / SBLOCK_WIDTH;
+        if sblock_start_pos >= sblock_end_pos {
+            let mid = (sblock_start_pos * SBLOCK_WIDTH).min(end);
+            return is_uniform_slice(start, mid - start) && is_uniform_slice(mid, end - mid);
+        }
+
+        let class = if b { SBLOCK_WIDTH } else { 0 };
+        (sblock_start_pos..sblock_end_pos)
+            .all(|j| self.sblocks.get_word(j, SBLOCK_SIZE) == class)
+            && is_uniform_slice(start, sblock_start_pos * SBLOCK_WIDTH - start)
+            && is_uniform_slice(sblock_end_pos * SBLOCK_WIDTH, end % SBLOCK_WIDTH)
     }
 
     fn find_lblock_pos(&self, b: bool, r: u64) -> usize {
@@ -314,12 +875,35 @@ impl BitVector {
     }
 }
 
+/// Truncates `array` to `len` bits, clearing the rest of the last block.
+fn truncate_bits(array: &mut BitArray, len: u64) {
+    array.truncate(len);
+    let excess = array.len().saturating_sub(len);
+    if excess != 0 {
+        array.set_slice(len, excess, 0);
+    }
+}
+
 enum EncodedIndex {
     Zero,
     Raw { bits: u64 },
     Packed { index: u64, sblock: NonZeroU8 },
 }
 
+impl EncodedIndex {
+    /// Decode bits up to `end` (`sblock[0..end]`).
+    /// Returns whole block when not packed.
+    fn decode(self, table: &ComboTable, end: NonZeroU32) -> u64 {
+        match self {
+            EncodedIndex::Zero => 0,
+            EncodedIndex::Raw { bits } => bits,
+            EncodedIndex::Packed { index, sblock } => {
+                table.decode_index(index, sblock.get().into(), end.get())
+            }
+        }
+    }
+}
+
 static TRUE: bool = true;
 static FALSE: bool = false;
 
@@ -335,6 +919,14 @@ impl Index<u64> for BitVector {
     }
 }
 
+impl<'a> Not for &'a BitVector {
+    type Output = Complement<&'a BitVector>;
+
+    fn not(self) -> Self::Output {
+        Complement::new(self)
+    }
+}
+
 impl FID for BitVector {
     fn len(&self) -> u64 {
         self.len
@@ -396,6 +988,11 @@ impl FID for BitVector {
         self.get_slice(i, size)
     }
 
+    /// Collects the words of [`BitVector::iter_words`].
+    fn to_words(&self) -> Vec<u64> {
+        self.iter_words().collect()
+    }
+
     fn rank1(&self, i: u64) -> u64 {
         if self.len <= i {
             return self.ones;
@@ -418,6 +1015,36 @@ impl FID for BitVector {
         rank + table.decode_rank1(index, sblock, (i - sblock_end_pos * SBLOCK_WIDTH) as u32) as u64
     }
 
+    fn count_ones(&self, range: Range<u64>) -> u64 {
+        let end = range.end.min(self.len);
+        let start = range.start.min(end);
+        if start / LBLOCK_WIDTH != end / LBLOCK_WIDTH {
+            return self.rank1(end) - self.rank1(start);
+        }
+
+        // Both ends are in the same large block, so scan its small blocks once.
+        let table = TABLE.as_ref();
+        let (mut pointer, _) = self.get_pointer_and_rank(table, start);
+        let mut sblock_pos = start / SBLOCK_WIDTH;
+        let skipped = self.sblock_rank1(table, sblock_pos, pointer, start % SBLOCK_WIDTH);
+        let mut ones = 0;
+        while sblock_pos < end / SBLOCK_WIDTH {
+            let k = self.sblocks.get_word(sblock_pos, SBLOCK_SIZE);
+            ones += k;
+            pointer += table.get_code_size(k as u32);
+            sblock_pos += 1;
+        }
+        ones + self.sblock_rank1(table, sblock_pos, pointer, end % SBLOCK_WIDTH) - skipped
+    }
+
+    fn is_all_ones(&self, range: Range<u64>) -> bool {
+        self.is_uniform(true, range)
+    }
+
+    fn is_all_zeros(&self, range: Range<u64>) -> bool {
+        self.is_uniform(false, range)
+    }
+
     fn select(&self, b: bool, r: u64) -> u64 {
         if b {
             self.select1(r)
@@ -433,6 +1060,10 @@ impl FID for BitVector {
     fn select1(&self, r: u64) -> u64 {
         self.select::<true>(r)
     }
+
+    fn has_fast_get_slice(&self) -> bool {
+        true
+    }
 }
 
 impl BitVector {
@@ -495,6 +1126,68 @@ impl<'i> IntoIterator for &'i BitVector {
     }
 }
 
+/// Iterator over the bits of a [`BitVector`] packed into 64-bit words.
+///
+/// Each word is a small block, so the index pointer is carried from one
+/// block to the next and only searched for after skipping with `nth`.
+/// The last word is padded with zeros.
+#[derive(Debug, Clone)]
+pub struct BitVectorWordIter<'i> {
+    bv: &'i BitVector,
+    /// Position of the next small block.
+    i: u64,
+    /// Index pointer of the next small block.
+    pointer: u64,
+}
+
+impl BitVectorWordIter<'_> {
+    fn end(&self) -> u64 {
+        self.bv.len.div_ceil(SBLOCK_WIDTH)
+    }
+}
+
+impl Iterator for BitVectorWordIter<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i >= self.end() {
+            return None;
+        }
+        let word = if self.i < self.bv.len / SBLOCK_WIDTH {
+            let table = TABLE.as_ref();
+            let sblock = self.bv.sblocks.get_word(self.i, SBLOCK_SIZE) as u8;
+            let end = NonZeroU32::new(SBLOCK_WIDTH as u32).unwrap();
+            let index = self.bv.read_index(table, sblock, self.pointer);
+            self.pointer += table.get_code_size(sblock.into());
+            index.decode(table, end)
+        } else {
+            self.bv.last_sblock_bits
+        };
+        self.i += 1;
+        Some(word)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        if n > 0 {
+            self.i = self.i.saturating_add(n as u64).min(self.end());
+            if self.i < self.bv.len / SBLOCK_WIDTH {
+                let pos = self.i * SBLOCK_WIDTH;
+                self.pointer = self.bv.get_pointer_and_rank(&TABLE, pos).0;
+            }
+        }
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let size = (self.end() - self.i).try_into().unwrap_or(usize::MAX);
+        (size, Some(size))
+    }
+}
+
+impl ExactSizeIterator for BitVectorWordIter<'_> {}
+
+impl FusedIterator for BitVectorWordIter<'_> {}
+
 impl From<&[bool]> for BitVector {
     fn from(value: &[bool]) -> Self {
         let mut vec = Self::with_capacity(value.len() as u64);
@@ -520,6 +1213,7 @@ impl FromIterator<bool> for BitVector {
 mod tests {
     use super::*;
     use crate::bit_arr;
+    use crate::fid_iter::FidWordIter;
     use rand::{Rng, SeedableRng, StdRng};
 
     const TEST_PROB: &[f64] = &[0.01, 0.5, 0.99];
@@ -675,6 +1369,222 @@ mod tests {
         });
     }
 
+    #[test]
+    fn to_words() {
+        gen_rng(|_, bv, ba| {
+            assert!(bv.iter_words().eq(FidWordIter::new(&bv)));
+            assert_eq!(bv.to_words(), bv.iter_words().collect::<Vec<_>>());
+            assert_eq!(bv.to_bit_array(), ba);
+        });
+    }
+
+    #[test]
+    fn iter_words() {
+        // Uniform small blocks of both bits between mixed ones, with a partial last word.
+        for n in [0, 1, SBLOCK_WIDTH, SBLOCK_WIDTH + 1, 3 * LBLOCK_WIDTH + 17] {
+            for (k, pattern) in [0u64, !0, 0x5555, 0xF0F0_0000_0000_0001].iter().enumerate() {
+                let bv: BitVector = (0..n)
+                    .map(|i| {
+                        let word = i / SBLOCK_WIDTH;
+                        match (word + k as u64) % 3 {
+                            0 => false,
+                            1 => true,
+                            _ => (pattern >> (i % SBLOCK_WIDTH)) & 1 == 1,
+                        }
+                    })
+                    .collect();
+                let expected: Vec<u64> = FidWordIter::new(&bv).collect();
+                assert_eq!(bv.iter_words().collect::<Vec<_>>(), expected);
+                assert_eq!(bv.iter_words().len(), expected.len());
+                for i in 0..=expected.len() {
+                    let mut words = bv.iter_words();
+                    assert_eq!(words.nth(i), expected.get(i).copied(), "nth({i})");
+                    assert!(words.eq(expected.iter().skip(i + 1).copied()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn push_slice() {
+        gen_rng(|n, bv, ba| {
+            for size in [1, 7, 33, 64] {
+                let mut vec = BitVector::new();
+                let mut i = 0;
+                while i < n {
+                    let size = size.min(n - i);
+                    vec.push_slice(ba.get_slice(i, size), size);
+                    i += size;
+                }
+                assert_eq!(vec, bv);
+            }
+        });
+    }
+
+    #[test]
+    fn from_words() {
+        gen_rng(|n, bv, ba| {
+            let words = ba.as_blocks();
+            assert_eq!(BitVector::from_words(words, n), bv);
+            assert_eq!(BitVector::from_words_parallel(words, n), bv);
+            for parts in [2, 3, 8] {
+                assert_eq!(BitVector::from_words_in_parts(words, n, parts), bv);
+            }
+        });
+    }
+
+    #[test]
+    fn set() {
+        gen_rng(|n, mut bv, mut ba| {
+            if n == 0 {
+                return;
+            }
+            let mut rng: StdRng = SeedableRng::from_seed([1; 32]);
+            for _ in 0..20 {
+                let i = rng.gen_range(0, n);
+                if rng.gen_bool(0.5) {
+                    let b = rng.gen_bool(0.5);
+                    bv.set(i, b);
+                    ba.set_bit(i, b);
+                } else {
+                    bv.flip(i);
+                    ba.set_bit(i, !ba.get_bit(i));
+                }
+                assert_eq!(bv, BitVector::from_words(ba.as_blocks(), n));
+            }
+        });
+    }
+
+    #[test]
+    fn set_crossing_select_units() {
+        // Every count before a large block is a multiple of `SELECT_UNIT_NUM / 4`,
+        // so flipping a bit and back crosses samples.
+        for b in [false, true] {
+            let n = 5 * SELECT_UNIT_NUM + 100;
+            let mut bv = BitVector::from_bit(b, n);
+            let mut ba = BitArray::from_bit(b, n);
+            for i in [0, 1023, 1024, SELECT_UNIT_NUM - 1, 3 * SELECT_UNIT_NUM, n - 1] {
+                for _ in 0..2 {
+                    bv.flip(i);
+                    ba.set_bit(i, !ba.get_bit(i));
+                    assert_eq!(bv, BitVector::from_words(ba.as_blocks(), n));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn truncate() {
+        gen_rng(|n, bv, ba| {
+            for len in [0, 1, 63, 64, 65, 1000, 1024, 4096, 5000, n / 2, n.saturating_sub(1)] {
+                let mut vec = bv.clone();
+                vec.truncate(len);
+                let len = len.min(n);
+                assert_eq!(vec, BitVector::from_words(ba.as_blocks(), len));
+            }
+
+            let mut vec = bv.clone();
+            for i in (n.saturating_sub(200)..n).rev() {
+                assert_eq!(vec.pop(), Some(ba.get_bit(i)));
+                assert_eq!(vec, BitVector::from_words(ba.as_blocks(), i));
+            }
+        });
+        assert_eq!(BitVector::new().pop(), None);
+    }
+
+    #[test]
+    fn append() {
+        gen_rng(|n, bv, _| {
+            for at in [0, 1, 64, 100, 1024, 2048 + 64, 5000, n / 2] {
+                let at = at.min(n);
+                let mut head = BitVector::from(&bv.to_vec()[..at as usize]);
+                let tail = BitVector::from(&bv.to_vec()[at as usize..]);
+                assert_eq!(BitVector::concat([&head, &tail]), bv);
+                head.append(&tail);
+                assert_eq!(head, bv);
+            }
+        });
+    }
+
+    #[test]
+    fn split_off() {
+        gen_rng(|n, bv, _| {
+            for at in [0, 1, 64, 100, 1024, 2048 + 64, 5000, n / 2, n] {
+                let at = at.min(n);
+                let mut head = bv.clone();
+                let tail = head.split_off(at);
+                assert_eq!(head, BitVector::from(&bv.to_vec()[..at as usize]));
+                assert_eq!(tail, BitVector::from(&bv.to_vec()[at as usize..]));
+            }
+        });
+    }
+
+    #[test]
+    fn count_range() {
+        gen_rng(|n, bv, ba| {
+            let mut rng: StdRng = SeedableRng::from_seed([2; 32]);
+            for _ in 0..200 {
+                let start = rng.gen_range(0, n + 1);
+                let max_len = [8, 130, 1100, n][rng.gen_range(0, 4)];
+                let end = (start + rng.gen_range(0, max_len + 1)).min(n);
+                let ones = (start..end).filter(|&i| ba.get_bit(i)).count() as u64;
+                let zeros = end - start - ones;
+                assert_eq!(bv.count_ones(start..end), ones);
+                assert_eq!(bv.count_zeros(start..end), zeros);
+                assert_eq!(bv.is_all_ones(start..end), zeros == 0);
+                assert_eq!(bv.is_all_zeros(start..end), ones == 0);
+                assert_eq!(bv.any(start..end), ones != 0);
+            }
+        });
+    }
+
+    #[test]
+    fn space_report() {
+        gen_rng(|n, bv, _| {
+            let report = bv.space_report();
+            assert!(report.total_bits() <= bv.encoded_bits());
+            assert_eq!(report.class_histogram.iter().sum::<u64>(), n / 64);
+            let packed_ones: u64 = report
+                .class_histogram
+                .iter()
+                .enumerate()
+                .map(|(k, &count)| k as u64 * count)
+                .sum();
+            assert_eq!(packed_ones, bv.rank1(n / 64 * 64));
+            assert!(report.entropy() <= 1.0);
+            assert!(!report.to_string().is_empty());
+        });
+        assert_eq!(bit_vec![false; 1000].space_report().entropy(), 0.0);
+    }
+
+    #[test]
+    fn estimate_encoded_bits() {
+        gen_rng(|n, mut bv, ba| {
+            bv.shrink_to_fit();
+            let estimate = BitVector::estimate_encoded_bits(ba.as_blocks(), n);
+            assert_eq!(estimate, bv.encoded_bits());
+        });
+    }
+
+    #[test]
+    fn space_report_empty() {
+        let report = BitVector::new().space_report();
+        assert_eq!(report.total_bits(), HEADER_SIZE);
+        assert_eq!(report.bits_per_bit(), 0.0);
+        assert_eq!(report.entropy(), 0.0);
+        let text = report.to_string();
+        assert!(!text.contains("NaN") && !text.contains("inf"), "{text}");
+    }
+
+    #[test]
+    fn invert() {
+        gen_rng(|_, mut bv, _| {
+            let inverted: Vec<bool> = bv.iter().map(|b| !b).collect();
+            bv.invert();
+            assert_eq!(bv, BitVector::from(inverted.as_slice()));
+        });
+    }
+
     #[cfg(feature = "serde")]
     #[cfg_attr(not(feature = "serde"), ignore)]
     #[test]
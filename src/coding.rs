@@ -91,6 +91,21 @@ impl ComboTable {
         (code, code_size)
     }
 
+    /// Returns the code of the complement of the block encoded with `code` in class `k`.
+    ///
+    /// Complementing every bit reverses the order of combinations,
+    /// so the code is mirrored within the `C(SBLOCK_WIDTH, k)` codes of its class.
+    pub fn invert_code(&self, code: u64, k: u32) -> u64 {
+        if self.get_code_size(k) == SBLOCK_WIDTH {
+            return !code;
+        }
+        let count = match k {
+            0 => 1,
+            _ => self.get_combination_size(0, k) + self.get_combination_size(0, k - 1),
+        };
+        count - 1 - code
+    }
+
     pub fn decode_index(&self, mut index: u64, mut k: u32, p: u32) -> u64 {
         assert!(p <= SBLOCK_WIDTH as u32);
 
@@ -0,0 +1,150 @@
+use crate::fid::FID;
+use crate::util::mask_u64;
+use std::ops::Range;
+
+/// A view of an [`FID`] with every bit inverted.
+///
+/// Operations on 0s and 1s are swapped, so `rank1` of the view is `rank0` of the
+/// inner bits. The view stores nothing besides the inner [`FID`].
+///
+/// # Examples
+///
+/// ```
+/// # use fid::{bit_vec, FID};
+/// // 01101101
+/// let bv = bit_vec![false, true, true, false, true, true, false, true];
+/// let inv = !&bv;
+/// assert_eq!(inv.rank1(5), bv.rank0(5));
+/// assert_eq!(inv.select1(2), bv.select0(2));
+/// assert_eq!(bv.complement().get_slice(0, 8), 0b0100_1001);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Complement<F> {
+    fid: F,
+}
+
+impl<F: FID> Complement<F> {
+    /// Constructs a new view with the bits of `fid` inverted.
+    pub fn new(fid: F) -> Self {
+        Complement { fid }
+    }
+
+    /// Returns a reference to the inner [`FID`].
+    pub fn inner(&self) -> &F {
+        &self.fid
+    }
+
+    /// Consumes the view, returning the inner [`FID`].
+    pub fn into_inner(self) -> F {
+        self.fid
+    }
+}
+
+impl<F: FID> FID for Complement<F> {
+    fn len(&self) -> u64 {
+        self.fid.len()
+    }
+
+    fn rank(&self, b: bool, i: u64) -> u64 {
+        self.fid.rank(!b, i)
+    }
+
+    fn rank0(&self, i: u64) -> u64 {
+        self.fid.rank1(i)
+    }
+
+    fn rank1(&self, i: u64) -> u64 {
+        self.fid.rank0(i)
+    }
+
+    fn count_ones(&self, range: Range<u64>) -> u64 {
+        self.fid.count_zeros(range)
+    }
+
+    fn count_zeros(&self, range: Range<u64>) -> u64 {
+        self.fid.count_ones(range)
+    }
+
+    fn is_all_ones(&self, range: Range<u64>) -> bool {
+        self.fid.is_all_zeros(range)
+    }
+
+    fn is_all_zeros(&self, range: Range<u64>) -> bool {
+        self.fid.is_all_ones(range)
+    }
+
+    fn any(&self, range: Range<u64>) -> bool {
+        !self.fid.is_all_ones(range)
+    }
+
+    fn select(&self, b: bool, r: u64) -> u64 {
+        self.fid.select(!b, r)
+    }
+
+    fn select_hint(&self, b: bool, r: u64, lo: u64, hi: u64) -> u64 {
+        self.fid.select_hint(!b, r, lo, hi)
+    }
+
+    fn min_select(&self, b: bool, r: u64) -> Option<u64> {
+        self.fid.min_select(!b, r)
+    }
+
+    fn select0(&self, r: u64) -> u64 {
+        self.fid.select1(r)
+    }
+
+    fn select1(&self, r: u64) -> u64 {
+        self.fid.select0(r)
+    }
+
+    fn get(&self, i: u64) -> bool {
+        !self.fid.get(i)
+    }
+
+    fn get_slice(&self, i: u64, size: u64) -> u64 {
+        !self.fid.get_slice(i, size) & mask_u64(size)
+    }
+
+    fn get_word(&self, i: u64, size: u64) -> u64 {
+        !self.fid.get_word(i, size) & mask_u64(size)
+    }
+
+    fn has_fast_get_slice(&self) -> bool {
+        self.fid.has_fast_get_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BitVector;
+    use rand::{Rng, SeedableRng, StdRng};
+
+    #[test]
+    fn complement() {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        for &p in &[0.01, 0.5, 0.99] {
+            let n = 10_000;
+            let bits: Vec<bool> = (0..n).map(|_| rng.gen_bool(p)).collect();
+            let bv = BitVector::from(bits.as_slice());
+            let inverted: Vec<bool> = bits.iter().map(|b| !b).collect();
+            let expected = BitVector::from(inverted.as_slice());
+
+            let inv = bv.complement();
+            assert_eq!(inv.len(), n);
+            for i in 0..=n {
+                assert_eq!(inv.rank1(i), expected.rank1(i));
+                assert_eq!(inv.rank(false, i), expected.rank0(i));
+            }
+            let ones = expected.rank1(n);
+            for r in 0..=ones {
+                assert_eq!(inv.select1(r), expected.select1(r));
+            }
+            for r in 0..=n - ones {
+                assert_eq!(inv.select(false, r), expected.select0(r));
+            }
+            assert_eq!(inv.to_words(), expected.to_words());
+            assert_eq!((!&bv).iter_runs().count(), expected.iter_runs().count());
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! Randomized differential tests of every [`FID`] implementor against [`NaiveFid`].
+//!
+//! A new implementor only needs a test calling [`check_implementor`]
+//! with a function building it from bits.
+
+use crate::*;
+use rand::{Rng, SeedableRng, StdRng};
+
+/// Lengths around the boundaries of small blocks, large blocks,
+/// select units and hybrid chunks.
+const LENGTHS: &[u64] = &[
+    0, 1, 2, 63, 64, 65, 127, 1023, 1024, 1025, 4095, 4096, 4097, 8193, 65_536, 65_537,
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Pattern {
+    /// Independent bits with the given density of 1s.
+    Random(f64),
+    /// Alternating runs with lengths up to the given maximum.
+    Clustered(u64),
+    Constant(bool),
+    Alternating,
+}
+
+const PATTERNS: &[Pattern] = &[
+    Pattern::Random(0.01),
+    Pattern::Random(0.5),
+    Pattern::Random(0.99),
+    Pattern::Clustered(8),
+    Pattern::Clustered(1000),
+    Pattern::Constant(false),
+    Pattern::Constant(true),
+    Pattern::Alternating,
+];
+
+/// Number of random seeds tried on top of every length and pattern.
+const EXTRA_SEEDS: u8 = 16;
+
+fn gen_bits(rng: &mut StdRng, n: u64, pattern: Pattern) -> Vec<bool> {
+    match pattern {
+        Pattern::Random(p) => (0..n).map(|_| rng.gen_bool(p)).collect(),
+        Pattern::Clustered(max_run) => {
+            let mut bits = Vec::with_capacity(n as usize);
+            let mut b = rng.gen_bool(0.5);
+            while (bits.len() as u64) < n {
+                let len = rng.gen_range(1, max_run + 1).min(n - bits.len() as u64);
+                bits.extend(std::iter::repeat_n(b, len as usize));
+                b = !b;
+            }
+            bits
+        }
+        Pattern::Constant(b) => vec![b; n as usize],
+        Pattern::Alternating => (0..n).map(|i| i % 2 == 1).collect(),
+    }
+}
+
+/// Runs `f` with the bits of every test case.
+fn for_each_case<F: FnMut(&[bool], &mut StdRng)>(mut f: F) {
+    for (k, &n) in LENGTHS.iter().enumerate() {
+        for (l, &pattern) in PATTERNS.iter().enumerate() {
+            let mut seed = [0; 32];
+            seed[0] = k as u8;
+            seed[1] = l as u8;
+            let mut rng: StdRng = SeedableRng::from_seed(seed);
+            let bits = gen_bits(&mut rng, n, pattern);
+            f(&bits, &mut rng);
+        }
+    }
+    for seed in 0..EXTRA_SEEDS {
+        let mut rng: StdRng = SeedableRng::from_seed([seed + 1; 32]);
+        let n = rng.gen_range(0, 5000);
+        let pattern = PATTERNS[rng.gen_range(0, PATTERNS.len())];
+        let bits = gen_bits(&mut rng, n, pattern);
+        f(&bits, &mut rng);
+    }
+}
+
+/// Checks the [`FID`] built by `build` against [`NaiveFid`] in every test case.
+fn check_implementor<T: FID, F: Fn(&[bool]) -> T>(build: F) {
+    for_each_case(|bits, rng| {
+        check_fid(&build(bits), &NaiveFid::from(bits), rng);
+    });
+}
+
+/// Returns all of `0..=end` when it is short,
+/// otherwise both ends and a random sample in between.
+fn sample(rng: &mut StdRng, end: u64) -> Vec<u64> {
+    const EDGE: u64 = 100;
+    if end <= 4 * EDGE {
+        return (0..=end).collect();
+    }
+    let mut samples: Vec<u64> = (0..EDGE).chain(end - EDGE..=end).collect();
+    samples.extend((0..2 * EDGE).map(|_| rng.gen_range(EDGE, end - EDGE)));
+    samples
+}
+
+/// Checks every method of `fid` against `naive`.
+fn check_fid<T: FID>(fid: &T, naive: &NaiveFid, rng: &mut StdRng) {
+    let n = naive.len();
+    assert_eq!(fid.len(), n);
+    assert_eq!(fid.is_empty(), n == 0);
+
+    for i in sample(rng, n) {
+        assert_eq!(fid.rank0(i), naive.rank0(i), "rank0({i})");
+        assert_eq!(fid.rank1(i), naive.rank1(i), "rank1({i})");
+        assert_eq!(fid.rank(false, i), naive.rank0(i), "rank(false, {i})");
+        assert_eq!(fid.rank(true, i), naive.rank1(i), "rank(true, {i})");
+    }
+
+    for b in [false, true] {
+        let count = naive.rank(b, n);
+        for r in sample(rng, count) {
+            let pos = naive.select(b, r);
+            assert_eq!(fid.select(b, r), pos, "select({b}, {r})");
+            let select = if b { fid.select1(r) } else { fid.select0(r) };
+            assert_eq!(select, pos, "select{}({r})", b as u8);
+            assert_eq!(fid.min_select(b, r), naive.min_select(b, r));
+            assert_eq!(fid.min_select(b, r + 1), naive.min_select(b, r + 1));
+
+            if r < count {
+                let lo = pos - rng.gen_range(0, pos.min(300) + 1);
+                let hi = pos + 1 + rng.gen_range(0, 300);
+                assert_eq!(fid.select_hint(b, r, lo, hi), pos, "select_hint({b}, {r})");
+            }
+        }
+    }
+
+    if n > 0 {
+        for i in sample(rng, n - 1) {
+            assert_eq!(fid.get(i), naive.get(i), "get({i})");
+        }
+    }
+    for size in [0, 1, 7, 33, 63, 64] {
+        if size <= n {
+            for i in sample(rng, n - size) {
+                let slice = naive.get_slice(i, size);
+                assert_eq!(fid.get_slice(i, size), slice, "get_slice({i}, {size})");
+            }
+        }
+    }
+    for size in [1, 7, 32, 64] {
+        if n / size > 0 {
+            for i in sample(rng, n / size - 1) {
+                let word = naive.get_word(i, size);
+                assert_eq!(fid.get_word(i, size), word, "get_word({i}, {size})");
+            }
+        }
+    }
+
+    for _ in 0..100 {
+        let start = rng.gen_range(0, n + 1);
+        let max_len = [8, 130, 1100, n][rng.gen_range(0, 4)];
+        let end = (start + rng.gen_range(0, max_len + 1)).min(n);
+        let range = start..end;
+        assert_eq!(
+            fid.count_ones(range.clone()),
+            naive.count_ones(range.clone())
+        );
+        assert_eq!(
+            fid.count_zeros(range.clone()),
+            naive.count_zeros(range.clone())
+        );
+        assert_eq!(
+            fid.is_all_ones(range.clone()),
+            naive.is_all_ones(range.clone())
+        );
+        assert_eq!(
+            fid.is_all_zeros(range.clone()),
+            naive.is_all_zeros(range.clone())
+        );
+        assert_eq!(fid.any(range.clone()), naive.any(range));
+    }
+
+    assert!(FidBitIter::new(fid).eq(naive.as_slice().iter().copied()));
+    assert_eq!(fid.to_words(), naive.to_words());
+    // Each run costs a select, so only the first runs are compared.
+    let runs = naive.iter_runs().take(1000);
+    assert!(fid.iter_runs().take(1000).eq(runs));
+    check_nth(FidBitIter::new(fid), naive.iter(), rng);
+    check_nth(FidWordIter::new(fid), naive.iter_words(), rng);
+}
+
+/// Checks `nth` by skipping random distances in both iterators.
+fn check_nth<I, J>(mut actual: I, mut expected: J, rng: &mut StdRng)
+where
+    I: Iterator + ExactSizeIterator,
+    J: Iterator<Item = I::Item>,
+    I::Item: PartialEq + std::fmt::Debug,
+{
+    let len = actual.len();
+    loop {
+        let skip = rng.gen_range(0, len / 8 + 2);
+        let item = actual.nth(skip);
+        assert_eq!(item, expected.nth(skip));
+        if item.is_none() {
+            break;
+        }
+    }
+}
+
+/// Implements only the required methods, relying on the defaults for everything else.
+struct DefaultsOnly(NaiveFid);
+
+impl FID for DefaultsOnly {
+    fn len(&self) -> u64 {
+        self.0.len()
+    }
+
+    fn rank1(&self, i: u64) -> u64 {
+        self.0.rank1(i)
+    }
+
+    fn get(&self, i: u64) -> bool {
+        self.0.get(i)
+    }
+}
+
+#[test]
+fn naive_fid_defaults() {
+    check_implementor(|bits| DefaultsOnly(NaiveFid::from(bits)));
+}
+
+#[test]
+fn bit_vector() {
+    check_implementor(|bits| BitVector::from(bits));
+}
+
+#[test]
+fn bit_vector_ref() {
+    for_each_case(|bits, rng| {
+        let bv = BitVector::from(bits);
+        check_fid(&&bv, &NaiveFid::from(bits), rng);
+    });
+}
+
+#[test]
+fn bit_vector_edited() {
+    check_implementor(|bits| {
+        // Build from two halves with flipped bits, then restore them.
+        let mid = bits.len() / 3;
+        let flipped: Vec<bool> = bits.iter().map(|b| !b).collect();
+        let mut bv = BitVector::from(&flipped[..mid]);
+        bv.invert();
+        bv.append(&BitVector::from(&bits[mid..]));
+        if let Some(&b) = bits.last() {
+            let last = bits.len() as u64 - 1;
+            bv.flip(last);
+            bv.set(last, b);
+        }
+        bv
+    });
+}
+
+#[test]
+fn rle_bit_vector() {
+    check_implementor(|bits| RleBitVector::from(bits));
+}
+
+#[test]
+fn hybrid_bitmap() {
+    check_implementor(|bits| HybridBitmap::from(bits));
+}
+
+#[test]
+fn bit_vector_snapshot() {
+    for_each_case(|bits, rng| {
+        let mut writer = BitVectorWriter::new();
+        let mut reader = writer.reader();
+        for (i, &b) in bits.iter().enumerate() {
+            writer.push(b);
+            if i % 3000 == 0 {
+                writer.publish();
+            }
+        }
+        let len = writer.publish() as usize;
+        reader.refresh();
+
+        let naive = NaiveFid::from(&bits[..len]);
+        check_fid(&reader, &naive, rng);
+        check_fid(reader.snapshot().as_ref(), &naive, rng);
+    });
+}
+
+#[test]
+fn complement() {
+    for_each_case(|bits, rng| {
+        let bv = BitVector::from(bits);
+        let naive: NaiveFid = bits.iter().map(|b| !b).collect();
+        check_fid(&bv.complement(), &naive, rng);
+    });
+}
+
+#[test]
+fn fid_slice() {
+    for_each_case(|bits, rng| {
+        let bv = BitVector::from(bits);
+        let n = bits.len() as u64;
+        let start = rng.gen_range(0, n + 1);
+        let end = rng.gen_range(start, n + 1);
+        let naive = NaiveFid::from(&bits[start as usize..end as usize]);
+        check_fid(&bv.slice(start..end), &naive, rng);
+    });
+}
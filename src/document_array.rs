@@ -0,0 +1,253 @@
+use crate::bit_vector::BitVector;
+use crate::fid::FID;
+use std::ops::Range;
+
+/// Maps positions in a concatenation of documents to documents and back.
+///
+/// The boundaries are marked in a [`BitVector`] with a 1 at the last position of
+/// each document, so the document containing position `pos` is `rank1(pos)`
+/// and document `d` ends after `select1(d)`. Documents cannot be empty.
+///
+/// # Examples
+///
+/// ```
+/// # use fid::DocumentArray;
+/// // "abc" "de" "fghi"
+/// let docs = DocumentArray::from_lengths([3, 2, 4]);
+/// assert_eq!(docs.locate(4), (1, 1));
+/// assert_eq!(docs.position(2, 0), 5);
+/// assert_eq!(docs.doc_range(1), 3..5);
+/// assert_eq!(docs.documents(2..6), 0..3);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemDbg, mem_dbg::MemSize))]
+pub struct DocumentArray {
+    /// A 1 at the last position of each document.
+    markers: BitVector,
+    doc_count: u64,
+}
+
+impl DocumentArray {
+    /// Constructs a new [`DocumentArray`] from the lengths of the documents.
+    ///
+    /// # Panics
+    /// * A length is 0.
+    pub fn from_lengths<I: IntoIterator<Item = u64>>(lengths: I) -> Self {
+        let mut markers = BitVector::new();
+        for len in lengths {
+            assert!(len > 0, "documents cannot be empty");
+            for _ in 1..len {
+                markers.push(false);
+            }
+            markers.push(true);
+        }
+        Self::from_markers(markers)
+    }
+
+    /// Constructs a new [`DocumentArray`] from bits with a 1 at the last position
+    /// of each document.
+    ///
+    /// # Panics
+    /// * The last bit is not a 1, so the last document has no end.
+    pub fn from_markers(mut markers: BitVector) -> Self {
+        let len = markers.len();
+        assert!(
+            len == 0 || markers.get(len - 1),
+            "the last document has no end marker"
+        );
+        markers.shrink_to_fit();
+        DocumentArray {
+            doc_count: markers.rank1(len),
+            markers,
+        }
+    }
+
+    /// Returns the total length of the documents.
+    pub fn len(&self) -> u64 {
+        self.markers.len()
+    }
+
+    /// Returns true if there are no documents.
+    pub fn is_empty(&self) -> bool {
+        self.doc_count == 0
+    }
+
+    /// Returns the number of documents.
+    pub fn doc_count(&self) -> u64 {
+        self.doc_count
+    }
+
+    /// Returns the boundary markers.
+    pub fn markers(&self) -> &BitVector {
+        &self.markers
+    }
+
+    /// Returns the document containing position `pos`.
+    ///
+    /// # Panics
+    /// * `pos` is out of bounds.
+    pub fn doc(&self, pos: u64) -> u64 {
+        assert!(pos < self.len(), "position {pos} is out of bounds");
+        self.markers.rank1(pos)
+    }
+
+    /// Returns the document containing position `pos` and the offset within it.
+    ///
+    /// # Panics
+    /// * `pos` is out of bounds.
+    pub fn locate(&self, pos: u64) -> (u64, u64) {
+        let doc = self.doc(pos);
+        (doc, pos - self.doc_start(doc))
+    }
+
+    /// Returns the position of `offset` in document `doc`.
+    ///
+    /// # Panics
+    /// * `doc` is out of bounds, or `offset` is past the end of the document.
+    pub fn position(&self, doc: u64, offset: u64) -> u64 {
+        let range = self.doc_range(doc);
+        assert!(
+            offset < range.end - range.start,
+            "offset {offset} is out of document {doc}"
+        );
+        range.start + offset
+    }
+
+    /// Returns the positions of document `doc`.
+    ///
+    /// # Panics
+    /// * `doc` is out of bounds.
+    pub fn doc_range(&self, doc: u64) -> Range<u64> {
+        assert!(doc < self.doc_count, "document {doc} is out of bounds");
+        self.doc_start(doc)..self.markers.select1(doc) + 1
+    }
+
+    /// Returns the length of document `doc`.
+    ///
+    /// # Panics
+    /// * `doc` is out of bounds.
+    pub fn doc_len(&self, doc: u64) -> u64 {
+        let range = self.doc_range(doc);
+        range.end - range.start
+    }
+
+    /// Returns the documents overlapping the positions in `range`,
+    /// which are consecutive.
+    ///
+    /// # Panics
+    /// * The range is out of bounds.
+    pub fn documents(&self, range: Range<u64>) -> Range<u64> {
+        assert!(
+            range.start <= range.end && range.end <= self.len(),
+            "range {range:?} is out of bounds"
+        );
+        if range.is_empty() {
+            return 0..0;
+        }
+        self.markers.rank1(range.start)..self.markers.rank1(range.end - 1) + 1
+    }
+
+    /// Returns the number of documents overlapping the positions in `range`.
+    ///
+    /// # Panics
+    /// * The range is out of bounds.
+    pub fn count_documents(&self, range: Range<u64>) -> u64 {
+        let docs = self.documents(range);
+        docs.end - docs.start
+    }
+
+    /// Returns the documents contained entirely within the positions in `range`.
+    ///
+    /// # Panics
+    /// * The range is out of bounds.
+    pub fn contained_documents(&self, range: Range<u64>) -> Range<u64> {
+        let Range { mut start, mut end } = self.documents(range.clone());
+        if start == end {
+            return 0..0;
+        }
+        if self.doc_start(start) < range.start {
+            start += 1;
+        }
+        if self.markers.select1(end - 1) + 1 > range.end {
+            end -= 1;
+        }
+        start..end.max(start)
+    }
+
+    fn doc_start(&self, doc: u64) -> u64 {
+        if doc == 0 {
+            0
+        } else {
+            self.markers.select1(doc - 1) + 1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, StdRng};
+
+    #[test]
+    fn mapping() {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        for &(n, max_len) in &[(0, 1), (1, 1), (10, 1), (100, 20), (1000, 300)] {
+            let lengths: Vec<u64> = (0..n).map(|_| rng.gen_range(1, max_len + 1)).collect();
+            let docs = DocumentArray::from_lengths(lengths.iter().copied());
+            let total: u64 = lengths.iter().sum();
+            assert_eq!(docs.doc_count(), n);
+            assert_eq!(docs.len(), total);
+
+            // Document and offset of every position.
+            let mut owners = Vec::new();
+            for (doc, &len) in lengths.iter().enumerate() {
+                let start = owners.len() as u64;
+                assert_eq!(docs.doc_range(doc as u64), start..start + len);
+                assert_eq!(docs.doc_len(doc as u64), len);
+                owners.extend((0..len).map(|offset| (doc as u64, offset)));
+            }
+            for (pos, &(doc, offset)) in owners.iter().enumerate() {
+                assert_eq!(docs.locate(pos as u64), (doc, offset));
+                assert_eq!(docs.position(doc, offset), pos as u64);
+            }
+
+            for _ in 0..200 {
+                let a = rng.gen_range(0, total + 1);
+                let b = rng.gen_range(0, total + 1);
+                let range = a.min(b)..a.max(b);
+                let overlapping: Vec<u64> = owners[range.start as usize..range.end as usize]
+                    .iter()
+                    .map(|&(doc, _)| doc)
+                    .collect::<std::collections::BTreeSet<_>>()
+                    .into_iter()
+                    .collect();
+                assert!(docs
+                    .documents(range.clone())
+                    .eq(overlapping.iter().copied()));
+                assert_eq!(
+                    docs.count_documents(range.clone()),
+                    overlapping.len() as u64
+                );
+
+                let contained: Vec<u64> = overlapping
+                    .into_iter()
+                    .filter(|&doc| {
+                        let doc_range = docs.doc_range(doc);
+                        range.start <= doc_range.start && doc_range.end <= range.end
+                    })
+                    .collect();
+                assert!(
+                    docs.contained_documents(range.clone()).eq(contained),
+                    "contained_documents({range:?})"
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn missing_end_marker() {
+        DocumentArray::from_markers(BitVector::from([true, false].as_slice()));
+    }
+}
@@ -0,0 +1,202 @@
+use crate::bit_array::BitArray;
+use crate::bit_vector::BitVector;
+use crate::fid::FID;
+use crate::util::{log2, mask_u64};
+
+/// Elias-Fano encoding of a non-decreasing sequence of integers.
+///
+/// Each value is split into `low_size` low bits, which are stored verbatim in `low`,
+/// and high bits, which are stored in unary in `high`: the `i`-th value sets
+/// the bit at `(value >> low_size) + i`. The sequence takes about
+/// `2 + log(universe / len)` bits per value.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemDbg, mem_dbg::MemSize))]
+pub(crate) struct EliasFano {
+    len: u64,
+    low_size: u64,
+    high: BitVector,
+    low: BitArray,
+}
+
+impl EliasFano {
+    /// Encodes a non-decreasing sequence of values.
+    pub fn from_slice(values: &[u64]) -> Self {
+        debug_assert!(values.is_sorted());
+
+        let len = values.len() as u64;
+        // The universe is `max + 1`, which may not fit in a `u64`.
+        let max = values.last().copied().unwrap_or(0);
+        let low_size = if len > 0 && max >= len {
+            log2(max / len) as u64
+        } else {
+            0
+        };
+
+        let mut high = BitVector::with_capacity(len + (max >> low_size) + 1);
+        let mut low = BitArray::with_word_capacity(low_size, len);
+        for (i, &value) in values.iter().enumerate() {
+            let pos = (value >> low_size) + i as u64;
+            while high.len() < pos {
+                high.push(false);
+            }
+            high.push(true);
+            low.set_word(i as u64, low_size, value & mask_u64(low_size));
+        }
+        high.shrink_to_fit();
+        low.shrink_to_fit();
+
+        EliasFano {
+            len,
+            low_size,
+            high,
+            low,
+        }
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns the `i`-th value.
+    ///
+    /// # Panics
+    /// * `i` is out of bounds.
+    pub fn get(&self, i: u64) -> u64 {
+        assert!(i < self.len);
+        let high = self.high.select1(i) - i;
+        (high << self.low_size) | self.low.get_word(i, self.low_size)
+    }
+
+    /// Returns the bits storing the high part of the values in unary.
+    pub fn high_bits(&self) -> &BitVector {
+        &self.high
+    }
+
+    /// Returns the low part of the `i`-th value.
+    pub fn low_bits(&self, i: u64) -> u64 {
+        self.low.get_word(i, self.low_size)
+    }
+
+    /// Returns the number of bits of the low part of the values.
+    pub fn low_size(&self) -> u64 {
+        self.low_size
+    }
+
+    /// Returns the number of values less than `x`.
+    ///
+    /// The values sharing the high part of `x` are located with two `select0`
+    /// on the high bits, then searched by their low part in `O(log n)`,
+    /// even when many values are equal.
+    pub fn rank(&self, x: u64) -> u64 {
+        let high = x >> self.low_size;
+        let start = if high == 0 {
+            0
+        } else {
+            let pos = self.high.select0(high - 1);
+            if pos >= self.high.len() {
+                return self.len;
+            }
+            pos + 1 - high
+        };
+        let pos = self.high.select0(high);
+        let end = if pos >= self.high.len() {
+            self.len
+        } else {
+            pos - high
+        };
+
+        let low = x & mask_u64(self.low_size);
+        let (mut lo, mut hi) = (start, end);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.low.get_word(mid, self.low_size) < low {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, StdRng};
+
+    fn gen_values(n: usize, max_gap: u64) -> Vec<u64> {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        let mut value = 0;
+        (0..n)
+            .map(|_| {
+                value += rng.gen_range(0, max_gap);
+                value
+            })
+            .collect()
+    }
+
+    #[test]
+    fn get() {
+        for &max_gap in &[1, 2, 100, 1 << 20] {
+            let values = gen_values(10_000, max_gap);
+            let ef = EliasFano::from_slice(&values);
+            assert_eq!(ef.len(), values.len() as u64);
+            for (i, &v) in values.iter().enumerate() {
+                assert_eq!(ef.get(i as u64), v);
+            }
+        }
+    }
+
+    #[test]
+    fn rank() {
+        for &max_gap in &[1, 2, 100] {
+            let values = gen_values(2_000, max_gap);
+            let ef = EliasFano::from_slice(&values);
+            let max = *values.last().unwrap();
+            for x in 0..max + 2 {
+                let ans = values.partition_point(|&v| v < x) as u64;
+                assert_eq!(ef.rank(x), ans);
+            }
+        }
+    }
+
+    #[test]
+    fn rank_duplicates() {
+        let mut values = vec![5; 100_000];
+        values.extend(std::iter::repeat_n(1 << 40, 100_000));
+        values.push(u64::MAX);
+        let ef = EliasFano::from_slice(&values);
+        for x in [0, 5, 6, 1 << 40, (1 << 40) + 1, u64::MAX - 1, u64::MAX] {
+            let ans = values.partition_point(|&v| v < x) as u64;
+            assert_eq!(ef.rank(x), ans, "rank({x})");
+        }
+    }
+
+    #[test]
+    fn max_value() {
+        for values in [
+            vec![u64::MAX],
+            vec![1, u64::MAX],
+            vec![0, u64::MAX - 1, u64::MAX, u64::MAX],
+        ] {
+            let ef = EliasFano::from_slice(&values);
+            for (i, &v) in values.iter().enumerate() {
+                assert_eq!(ef.get(i as u64), v);
+            }
+            for x in [0, 1, 2, u64::MAX - 1, u64::MAX] {
+                let ans = values.partition_point(|&v| v < x) as u64;
+                assert_eq!(ef.rank(x), ans);
+            }
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let ef = EliasFano::from_slice(&[]);
+        assert_eq!(ef.len(), 0);
+        assert_eq!(ef.rank(0), 0);
+        assert_eq!(ef.rank(100), 0);
+    }
+}
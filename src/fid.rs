@@ -1,3 +1,11 @@
+use crate::bit_array::BitArray;
+use crate::coding::ComboTable;
+use crate::complement::Complement;
+use crate::fid_iter::{FidRunIter, FidWordIter};
+use crate::fid_slice::FidSlice;
+use crate::util::phi_sub;
+use std::ops::Range;
+
 /// Fully Indexable Dictionary of bits that supports rank and select operations.
 pub trait FID {
     /// Returns the total number of bits.
@@ -27,20 +35,80 @@ pub trait FID {
         i - self.rank0(i)
     }
 
+    /// Count the number of ones in `range`.
+    fn count_ones(&self, range: Range<u64>) -> u64 {
+        let end = range.end.min(self.len());
+        let start = range.start.min(end);
+        self.rank1(end) - self.rank1(start)
+    }
+
+    /// Count the number of zeroes in `range`.
+    fn count_zeros(&self, range: Range<u64>) -> u64 {
+        let end = range.end.min(self.len());
+        let start = range.start.min(end);
+        (end - start) - self.count_ones(start..end)
+    }
+
+    /// Returns true if all bits in `range` are ones.
+    fn is_all_ones(&self, range: Range<u64>) -> bool {
+        self.count_zeros(range) == 0
+    }
+
+    /// Returns true if all bits in `range` are zeroes.
+    fn is_all_zeros(&self, range: Range<u64>) -> bool {
+        self.count_ones(range) == 0
+    }
+
+    /// Returns true if any bit in `range` is one.
+    fn any(&self, range: Range<u64>) -> bool {
+        !self.is_all_zeros(range)
+    }
+
     /// Locate the position of the `(r + 1)`-th bit.
+    ///
+    /// Returns the length if there is no such bit.
+    ///
+    /// The default bisects on [`rank`]. If [`has_fast_get_slice`] is true,
+    /// it stops once the range fits in a word, which is scanned with [`get_slice`].
+    ///
+    /// [`rank`]: FID::rank
+    /// [`has_fast_get_slice`]: FID::has_fast_get_slice
+    /// [`get_slice`]: FID::get_slice
     fn select(&self, b: bool, r: u64) -> u64 {
-        let (mut s, mut e) = (0, self.len());
+        bisect_select(self, b, r, 0, 0, self.len())
+    }
 
-        while e - s > 1 {
-            let m = (s + e) / 2;
+    /// Locate the position of the `(r + 1)`-th bit, knowing that it lies in `[lo, hi)`.
+    ///
+    /// Returns the length if there is no such bit in `[lo, hi)`.
+    ///
+    /// The default does an exponential search from `lo`, so it is cheap
+    /// for callers with locality even when `hi` is a loose bound.
+    fn select_hint(&self, b: bool, r: u64, lo: u64, hi: u64) -> u64 {
+        let hi = hi.min(self.len());
+        if hi <= lo {
+            return self.len();
+        }
+        let mut lo = lo;
+        let mut rank_lo = self.rank(b, lo);
+        if r < rank_lo {
+            return self.len();
+        }
+
+        let mut step = u64::BITS as u64;
+        let mut end = hi;
+        while step < hi - lo {
+            let m = lo + step;
             let rank = self.rank(b, m);
             if r < rank {
-                e = m
-            } else {
-                s = m
+                end = m;
+                break;
             }
+            lo = m;
+            rank_lo = rank;
+            step *= 2;
         }
-        s
+        bisect_select(self, b, r, lo, rank_lo, end)
     }
 
     /// Locate the min position of `r`-th bit 
@@ -92,4 +160,334 @@ pub trait FID {
     fn get_word(&self, i: u64, size: u64) -> u64 {
         self.get_slice(i * size, size)
     }
+
+    /// Returns true if [`get_slice`] costs about as much as a single [`rank`].
+    ///
+    /// The default [`get_slice`] calls [`get`] for every bit, so the default is false
+    /// and implementors overriding [`get_slice`] should return true.
+    ///
+    /// [`get_slice`]: FID::get_slice
+    /// [`rank`]: FID::rank
+    /// [`get`]: FID::get
+    fn has_fast_get_slice(&self) -> bool {
+        false
+    }
+
+    /// Returns an iterator over the bits packed into 64-bit words.
+    ///
+    /// The last word is padded with zeros.
+    fn iter_words(&self) -> FidWordIter<'_, Self>
+    where
+        Self: Sized,
+    {
+        FidWordIter::new(self)
+    }
+
+    /// Collects the bits into 64-bit words, see [`iter_words`].
+    ///
+    /// [`iter_words`]: FID::iter_words
+    fn to_words(&self) -> Vec<u64> {
+        FidWordIter::new(self).collect()
+    }
+
+    /// Collects the bits into a [`BitArray`], see [`iter_words`].
+    ///
+    /// [`iter_words`]: FID::iter_words
+    fn to_bit_array(&self) -> BitArray
+    where
+        Self: Sized,
+    {
+        BitArray::from_blocks(self.to_words())
+    }
+
+    /// Returns an iterator over the maximal runs of equal bits as `(bit, start, len)`.
+    ///
+    /// Each run costs one [`select`] call, which makes this much faster
+    /// than iterating bits when runs are long.
+    ///
+    /// [`select`]: FID::select
+    fn iter_runs(&self) -> FidRunIter<'_, Self>
+    where
+        Self: Sized,
+    {
+        FidRunIter::new(self)
+    }
+
+    /// Counts the maximal runs of equal bits, see [`iter_runs`].
+    ///
+    /// [`iter_runs`]: FID::iter_runs
+    fn count_runs(&self) -> u64
+    where
+        Self: Sized,
+    {
+        self.iter_runs().count() as u64
+    }
+
+    /// Returns a view of the bits in `range`, see [`FidSlice`].
+    ///
+    /// # Panics
+    /// * `range` is decreasing or its end exceeds the length.
+    fn slice(&self, range: Range<u64>) -> FidSlice<'_, Self>
+    where
+        Self: Sized,
+    {
+        FidSlice::new(self, range)
+    }
+
+    /// Returns a view with every bit inverted, see [`Complement`].
+    fn complement(&self) -> Complement<&Self>
+    where
+        Self: Sized,
+    {
+        Complement::new(self)
+    }
+}
+
+/// Locate the `(r + 1)`-th bit in `[lo, hi)`, where `rank_lo` is the rank at `lo`.
+fn bisect_select<T: FID + ?Sized>(fid: &T, b: bool, r: u64, lo: u64, rank_lo: u64, hi: u64) -> u64 {
+    // Scanning a word bit by bit would take more rank calls than bisecting it.
+    let scan_size = if fid.has_fast_get_slice() {
+        u64::BITS as u64
+    } else {
+        1
+    };
+    let (mut lo, mut rank_lo, mut hi) = (lo, rank_lo, hi);
+    while hi - lo > scan_size {
+        let m = lo + (hi - lo) / 2;
+        let rank = fid.rank(b, m);
+        if r < rank {
+            hi = m;
+        } else {
+            lo = m;
+            rank_lo = rank;
+        }
+    }
+
+    let size = hi - lo;
+    let word = fid.get_slice(lo, size);
+    let count = phi_sub(b, size, word.count_ones() as u64);
+    let r = r - rank_lo;
+    if r < count {
+        let bits = if b { !word } else { word };
+        lo + ComboTable::select0_raw(bits, r as u32) as u64
+    } else {
+        fid.len()
+    }
+}
+
+impl<T: FID + ?Sized> FID for &T {
+    fn len(&self) -> u64 {
+        (**self).len()
+    }
+
+    fn rank(&self, b: bool, i: u64) -> u64 {
+        (**self).rank(b, i)
+    }
+
+    fn rank0(&self, i: u64) -> u64 {
+        (**self).rank0(i)
+    }
+
+    fn rank1(&self, i: u64) -> u64 {
+        (**self).rank1(i)
+    }
+
+    fn count_ones(&self, range: Range<u64>) -> u64 {
+        (**self).count_ones(range)
+    }
+
+    fn count_zeros(&self, range: Range<u64>) -> u64 {
+        (**self).count_zeros(range)
+    }
+
+    fn is_all_ones(&self, range: Range<u64>) -> bool {
+        (**self).is_all_ones(range)
+    }
+
+    fn is_all_zeros(&self, range: Range<u64>) -> bool {
+        (**self).is_all_zeros(range)
+    }
+
+    fn any(&self, range: Range<u64>) -> bool {
+        (**self).any(range)
+    }
+
+    fn select(&self, b: bool, r: u64) -> u64 {
+        (**self).select(b, r)
+    }
+
+    fn select_hint(&self, b: bool, r: u64, lo: u64, hi: u64) -> u64 {
+        (**self).select_hint(b, r, lo, hi)
+    }
+
+    fn min_select(&self, b: bool, r: u64) -> Option<u64> {
+        (**self).min_select(b, r)
+    }
+
+    fn select0(&self, r: u64) -> u64 {
+        (**self).select0(r)
+    }
+
+    fn select1(&self, r: u64) -> u64 {
+        (**self).select1(r)
+    }
+
+    fn get(&self, i: u64) -> bool {
+        (**self).get(i)
+    }
+
+    fn get_slice(&self, i: u64, size: u64) -> u64 {
+        (**self).get_slice(i, size)
+    }
+
+    fn get_word(&self, i: u64, size: u64) -> u64 {
+        (**self).get_word(i, size)
+    }
+
+    fn has_fast_get_slice(&self) -> bool {
+        (**self).has_fast_get_slice()
+    }
+
+    fn to_words(&self) -> Vec<u64> {
+        (**self).to_words()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, StdRng};
+    use std::cell::Cell;
+
+    /// Implements only `rank1` and `get`, relying on the default `select`.
+    struct PrefixSums {
+        bits: Vec<bool>,
+        ranks: Vec<u64>,
+    }
+
+    impl PrefixSums {
+        fn new(bits: Vec<bool>) -> Self {
+            let ranks = std::iter::once(0)
+                .chain(bits.iter().scan(0, |rank, &b| {
+                    *rank += b as u64;
+                    Some(*rank)
+                }))
+                .collect();
+            PrefixSums { bits, ranks }
+        }
+    }
+
+    impl FID for PrefixSums {
+        fn len(&self) -> u64 {
+            self.bits.len() as u64
+        }
+
+        fn rank1(&self, i: u64) -> u64 {
+            self.ranks[i.min(self.len()) as usize]
+        }
+
+        fn get(&self, i: u64) -> bool {
+            self.bits[i as usize]
+        }
+    }
+
+    /// Implements only `rank1`, counting the calls.
+    struct RankOnly {
+        sums: PrefixSums,
+        calls: Cell<u64>,
+    }
+
+    impl FID for RankOnly {
+        fn len(&self) -> u64 {
+            self.sums.len()
+        }
+
+        fn rank1(&self, i: u64) -> u64 {
+            self.calls.set(self.calls.get() + 1);
+            self.sums.rank1(i)
+        }
+    }
+
+    #[test]
+    fn default_select_rank_calls() {
+        let bits = (0..1 << 16).map(|i| i % 3 == 0).collect();
+        let fid = RankOnly {
+            sums: PrefixSums::new(bits),
+            calls: Cell::new(0),
+        };
+        for r in [0, 100, 10_000, 21_845] {
+            fid.calls.set(0);
+            assert_eq!(fid.select1(r), 3 * r);
+            // Bisecting down to a single bit, which `get` reads with two more calls.
+            assert!(fid.calls.get() <= 16 + 2, "{} calls", fid.calls.get());
+        }
+    }
+
+    /// Overrides `to_words` with words that differ from its bits.
+    struct Overridden;
+
+    impl FID for Overridden {
+        fn len(&self) -> u64 {
+            1
+        }
+
+        fn rank1(&self, i: u64) -> u64 {
+            i.min(1)
+        }
+
+        fn get(&self, _: u64) -> bool {
+            true
+        }
+
+        fn to_words(&self) -> Vec<u64> {
+            vec![7]
+        }
+    }
+
+    #[test]
+    fn ref_forwards_overrides() {
+        fn words<T: FID>(fid: T) -> Vec<u64> {
+            fid.to_words()
+        }
+
+        let bv: crate::BitVector = (0..1000).map(|i| i % 3 == 0).collect();
+        let (r, rr) = (&bv, &&bv);
+        assert_eq!(words(r), bv.to_words());
+        assert_eq!(words(rr), bv.to_words());
+        assert!(r.has_fast_get_slice());
+
+        let (r, rr) = (&Overridden, &&Overridden);
+        assert_eq!(words(r), [7]);
+        assert_eq!(words(rr), [7]);
+        assert_eq!((r as &dyn FID).to_words(), [7]);
+        assert_eq!(words(Overridden.complement()), [0]);
+    }
+
+    #[test]
+    fn default_select() {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        for &n in &[0, 1, 63, 64, 65, 1000, 5000] {
+            for &p in &[0.01, 0.5, 0.99] {
+                let bits: Vec<bool> = (0..n).map(|_| rng.gen_bool(p)).collect();
+                let fid = PrefixSums::new(bits.clone());
+                for b in [false, true] {
+                    let positions: Vec<u64> = (0..n).filter(|&i| bits[i as usize] == b).collect();
+                    for (r, &pos) in positions.iter().enumerate() {
+                        assert_eq!(fid.select(b, r as u64), pos);
+                        assert_eq!(
+                            fid.select_hint(b, r as u64, pos.saturating_sub(100), n),
+                            pos
+                        );
+                        assert_eq!(fid.select_hint(b, r as u64, 0, pos + 1), pos);
+                    }
+                    let count = positions.len() as u64;
+                    assert_eq!(fid.select(b, count), n);
+                    if let Some(&pos) = positions.last() {
+                        assert_eq!(fid.select_hint(b, count - 1, 0, pos), n);
+                        assert_eq!(fid.select_hint(b, 0, pos + 1, n), n);
+                    }
+                }
+            }
+        }
+    }
 }
@@ -6,8 +6,9 @@ const BLOCK_SIZE: u64 = 64;
 #[derive(Debug, Clone)]
 pub struct FidBitIter<'i, T: FID> {
     fid: &'i T,
-    len: u64,
     i: u64,
+    end: u64,
+    /// Word containing bit `i`, only loaded once `i` is past its start.
     bits: u64,
 }
 
@@ -15,39 +16,21 @@ impl<'i, T: FID> FidBitIter<'i, T> {
     pub fn new(fid: &'i T) -> Self {
         Self {
             fid,
-            len: fid.len(),
             i: 0,
+            end: fid.len(),
             bits: 0,
         }
     }
 
     #[cold]
-    fn refill(&mut self) -> bool {
-        if self.len >= BLOCK_SIZE {
-            self.bits = self.fid.get_word(self.i / BLOCK_SIZE, BLOCK_SIZE);
-            self.len -= BLOCK_SIZE;
-        } else if self.len > 0 {
-            self.refill_tail();
+    fn refill(&mut self) {
+        let start = self.i - self.i % BLOCK_SIZE;
+        let size = (self.end - start).min(BLOCK_SIZE);
+        self.bits = if size == BLOCK_SIZE {
+            self.fid.get_word(start / BLOCK_SIZE, BLOCK_SIZE)
         } else {
-            return false;
+            self.fid.get_slice(start, size)
         };
-        return true;
-    }
-
-    fn refill_tail(&mut self) {
-        let offset = BLOCK_SIZE - self.len;
-        self.bits = self.fid.get_slice(self.i, self.len) << offset;
-
-        // Adjust `i` to refill early since we're at the end.
-        self.i = (self.i / BLOCK_SIZE) * BLOCK_SIZE + offset;
-        self.len = 0;
-    }
-
-    #[inline(always)]
-    fn next_bit(&mut self) -> Option<bool> {
-        let bit = self.bits & (1 << (self.i % BLOCK_SIZE));
-        self.i += 1;
-        Some(bit != 0)
     }
 }
 
@@ -55,29 +38,31 @@ impl<'i, T: FID> Iterator for FidBitIter<'i, T> {
     type Item = bool;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.i % BLOCK_SIZE == 0 && !self.refill() {
+        if self.i >= self.end {
             return None;
         }
-        self.next_bit()
+        if self.i.is_multiple_of(BLOCK_SIZE) {
+            self.refill();
+        }
+        let bit = self.bits & (1 << (self.i % BLOCK_SIZE));
+        self.i += 1;
+        Some(bit != 0)
     }
 
     fn nth(&mut self, n: usize) -> Option<Self::Item> {
         let prev = self.i;
-        let size = self.len.min(n as u64); // prevent overflows
-        self.i += size;
-        self.len -= size;
-
-        // Refill if block boundary was crossed *or* first block is not loaded.
-        if (self.i / BLOCK_SIZE > prev / BLOCK_SIZE) || (prev % BLOCK_SIZE == 0) {
-            if !self.refill() {
-                return None;
-            }
+        self.i = self.i.saturating_add(n as u64).min(self.end);
+
+        // Refill unless the word is still loaded or `next` loads it anyway.
+        let loaded = !prev.is_multiple_of(BLOCK_SIZE) && self.i / BLOCK_SIZE == prev / BLOCK_SIZE;
+        if self.i < self.end && !self.i.is_multiple_of(BLOCK_SIZE) && !loaded {
+            self.refill();
         }
-        self.next_bit()
+        self.next()
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let size = self.len.try_into().unwrap_or(usize::MAX);
+        let size = (self.end - self.i).try_into().unwrap_or(usize::MAX);
         (size, Some(size))
     }
 }
@@ -86,10 +71,129 @@ impl<'i, T: FID> ExactSizeIterator for FidBitIter<'i, T> {}
 
 impl<'i, T: FID> FusedIterator for FidBitIter<'i, T> {}
 
+/// Iterator over the bits of a [`FID`] packed into 64-bit words.
+///
+/// The last word is padded with zeros.
+#[derive(Debug, Clone)]
+pub struct FidWordIter<'i, T: FID + ?Sized> {
+    fid: &'i T,
+    i: u64,
+    end: u64,
+}
+
+impl<'i, T: FID + ?Sized> FidWordIter<'i, T> {
+    pub fn new(fid: &'i T) -> Self {
+        Self {
+            fid,
+            i: 0,
+            end: fid.len().div_ceil(BLOCK_SIZE),
+        }
+    }
+
+    fn get(&self, i: u64) -> u64 {
+        let len = self.fid.len();
+        if (i + 1) * BLOCK_SIZE <= len {
+            self.fid.get_word(i, BLOCK_SIZE)
+        } else {
+            let start = i * BLOCK_SIZE;
+            self.fid.get_slice(start, len - start)
+        }
+    }
+}
+
+impl<'i, T: FID + ?Sized> Iterator for FidWordIter<'i, T> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i >= self.end {
+            return None;
+        }
+        let word = self.get(self.i);
+        self.i += 1;
+        Some(word)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.i = self.i.saturating_add(n as u64).min(self.end);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let size = (self.end - self.i).try_into().unwrap_or(usize::MAX);
+        (size, Some(size))
+    }
+}
+
+impl<'i, T: FID + ?Sized> ExactSizeIterator for FidWordIter<'i, T> {}
+
+impl<'i, T: FID + ?Sized> FusedIterator for FidWordIter<'i, T> {}
+
+/// Iterator over the maximal runs of equal bits in a [`FID`].
+///
+/// Each run is yielded as `(bit, start, len)`. Runs are found with
+/// [`FID::select`] instead of decoding every bit.
+#[derive(Debug, Clone)]
+pub struct FidRunIter<'i, T: FID> {
+    fid: &'i T,
+    len: u64,
+    i: u64,
+    bit: bool,
+    /// Number of 0s and 1s in `[0..i)`.
+    ranks: [u64; 2],
+    /// Number of 0s and 1s in total.
+    counts: [u64; 2],
+}
+
+impl<'i, T: FID> FidRunIter<'i, T> {
+    pub fn new(fid: &'i T) -> Self {
+        let len = fid.len();
+        let ones = fid.rank1(len);
+        Self {
+            fid,
+            len,
+            i: 0,
+            bit: len != 0 && fid.get(0),
+            ranks: [0, 0],
+            counts: [len - ones, ones],
+        }
+    }
+}
+
+impl<'i, T: FID> Iterator for FidRunIter<'i, T> {
+    type Item = (bool, u64, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i >= self.len {
+            return None;
+        }
+
+        let b = self.bit;
+        let rank = self.ranks[!b as usize];
+        let end = if rank < self.counts[!b as usize] {
+            self.fid.select(!b, rank)
+        } else {
+            self.len
+        };
+
+        let start = self.i;
+        self.ranks[b as usize] += end - start;
+        self.i = end;
+        self.bit = !b;
+        Some((b, start, end - start))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let rest = (self.len - self.i).try_into().unwrap_or(usize::MAX);
+        ((rest != 0) as usize, Some(rest))
+    }
+}
+
+impl<'i, T: FID> FusedIterator for FidRunIter<'i, T> {}
+
 #[cfg(test)]
 mod tests {
     use rand::{Rng, SeedableRng, StdRng};
-    use crate::BitVector;
+    use crate::{BitVector, FID};
 
     use super::BLOCK_SIZE;
 
@@ -137,4 +241,81 @@ mod tests {
             assert_eq!(None, bv.into_iter().nth(n as usize));
         }
     }
+
+    #[test]
+    fn iter_words() {
+        for &n in TEST_SIZE {
+            let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+            let mut bv = BitVector::new();
+            let mut vec = Vec::new();
+            for _ in 0..n {
+                let b = rng.gen_bool(0.5);
+                vec.push(b);
+                bv.push(b);
+
+                let words: Vec<_> = vec
+                    .chunks(BLOCK_SIZE as usize)
+                    .map(|c| c.iter().rev().fold(0, |acc, b| (acc << 1) | *b as u64))
+                    .collect();
+                assert_eq!(words, bv.iter_words().collect::<Vec<_>>());
+                assert_eq!(words.len(), bv.iter_words().len());
+            }
+        }
+    }
+
+    #[test]
+    fn iter_words_nth() {
+        let bv: BitVector = (0..BLOCK_SIZE * 3 + 5).map(|i| i % 3 == 0).collect();
+        let words = bv.to_words();
+        for (i, &word) in words.iter().enumerate() {
+            assert_eq!(bv.iter_words().nth(i), Some(word));
+        }
+        assert_eq!(bv.iter_words().nth(words.len()), None);
+    }
+
+    fn naive_runs(vec: &[bool]) -> Vec<(bool, u64, u64)> {
+        let mut runs: Vec<(bool, u64, u64)> = Vec::new();
+        for (i, &b) in vec.iter().enumerate() {
+            match runs.last_mut() {
+                Some((bit, _, len)) if *bit == b => *len += 1,
+                _ => runs.push((b, i as u64, 1)),
+            }
+        }
+        runs
+    }
+
+    #[test]
+    fn iter_runs() {
+        for &n in TEST_SIZE {
+            let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+            let mut bv = BitVector::new();
+            let mut vec = Vec::new();
+            for _ in 0..n {
+                let b = rng.gen_bool(0.5);
+                vec.push(b);
+                bv.push(b);
+
+                let runs = naive_runs(&vec);
+                assert_eq!(runs, bv.iter_runs().collect::<Vec<_>>());
+                assert_eq!(runs.len() as u64, bv.count_runs());
+            }
+        }
+    }
+
+    #[test]
+    fn iter_runs_clustered() {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        let mut vec = Vec::new();
+        let mut b = false;
+        while vec.len() < 100_000 {
+            let len = rng.gen_range(1, 5000);
+            vec.extend(std::iter::repeat_n(b, len));
+            b = !b;
+        }
+        let bv = BitVector::from(vec.as_slice());
+
+        let runs = naive_runs(&vec);
+        assert_eq!(runs, bv.iter_runs().collect::<Vec<_>>());
+        assert_eq!(runs.len() as u64, bv.count_runs());
+    }
 }
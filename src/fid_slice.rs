@@ -0,0 +1,200 @@
+use crate::fid::FID;
+use crate::fid_iter::FidBitIter;
+use std::ops::Range;
+
+/// A view of the bits `[start, end)` of an [`FID`].
+///
+/// Positions and ranks are relative to the window: `rank1(i)` counts the 1s in
+/// `[start, start + i)` and `select1(r)` locates the `(r + 1)`-th 1 after `start`.
+/// The rank at `start` is computed once when the view is created.
+///
+/// # Examples
+///
+/// ```
+/// # use fid::{bit_vec, FID};
+/// // 01101101
+/// let bv = bit_vec![false, true, true, false, true, true, false, true];
+/// let slice = bv.slice(2..7);
+/// assert_eq!(slice.len(), 5);
+/// assert_eq!(slice.rank1(3), 2);
+/// assert_eq!(slice.select0(1), 4);
+/// assert_eq!(slice.select1(3), 5);
+/// ```
+#[derive(Debug)]
+pub struct FidSlice<'a, T: FID> {
+    fid: &'a T,
+    start: u64,
+    end: u64,
+    /// Number of 1s before `start`.
+    rank_start: u64,
+    /// Number of 1s in the window.
+    ones: u64,
+}
+
+impl<'a, T: FID> FidSlice<'a, T> {
+    /// Constructs a new view of the bits in `range`.
+    ///
+    /// # Panics
+    /// * `range` is decreasing or its end exceeds the length of `fid`.
+    pub fn new(fid: &'a T, range: Range<u64>) -> Self {
+        assert!(range.start <= range.end && range.end <= fid.len());
+
+        let rank_start = fid.rank1(range.start);
+        FidSlice {
+            fid,
+            start: range.start,
+            end: range.end,
+            rank_start,
+            ones: fid.rank1(range.end) - rank_start,
+        }
+    }
+
+    /// Returns the range of the window in the inner [`FID`].
+    pub fn range(&self) -> Range<u64> {
+        self.start..self.end
+    }
+
+    /// Maps a range of the window to the inner [`FID`], clamped to the window.
+    fn inner_range(&self, range: Range<u64>) -> Range<u64> {
+        let end = range.end.min(self.len());
+        let start = range.start.min(end);
+        self.start + start..self.start + end
+    }
+
+    pub fn iter(&self) -> FidBitIter<'_, Self> {
+        FidBitIter::new(self)
+    }
+
+    pub fn to_vec(&self) -> Vec<bool> {
+        self.iter().collect()
+    }
+}
+
+impl<T: FID> Clone for FidSlice<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: FID> Copy for FidSlice<'_, T> {}
+
+impl<T: FID> FID for FidSlice<'_, T> {
+    fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    fn rank1(&self, i: u64) -> u64 {
+        if self.len() <= i {
+            return self.ones;
+        }
+        self.fid.rank1(self.start + i) - self.rank_start
+    }
+
+    fn rank0(&self, i: u64) -> u64 {
+        let i = i.min(self.len());
+        i - self.rank1(i)
+    }
+
+    fn count_ones(&self, range: Range<u64>) -> u64 {
+        self.fid.count_ones(self.inner_range(range))
+    }
+
+    fn is_all_ones(&self, range: Range<u64>) -> bool {
+        self.fid.is_all_ones(self.inner_range(range))
+    }
+
+    fn is_all_zeros(&self, range: Range<u64>) -> bool {
+        self.fid.is_all_zeros(self.inner_range(range))
+    }
+
+    fn select(&self, b: bool, r: u64) -> u64 {
+        if b {
+            self.select1(r)
+        } else {
+            self.select0(r)
+        }
+    }
+
+    fn select0(&self, r: u64) -> u64 {
+        if self.len() - self.ones <= r {
+            return self.len();
+        }
+        let zeros_start = self.start - self.rank_start;
+        self.fid.select0(zeros_start + r) - self.start
+    }
+
+    fn select1(&self, r: u64) -> u64 {
+        if self.ones <= r {
+            return self.len();
+        }
+        self.fid.select1(self.rank_start + r) - self.start
+    }
+
+    fn get(&self, i: u64) -> bool {
+        debug_assert!(i < self.len());
+        self.fid.get(self.start + i)
+    }
+
+    fn get_slice(&self, i: u64, size: u64) -> u64 {
+        debug_assert!(size <= 64);
+
+        let slice_end = i + size;
+        assert!(slice_end <= self.len());
+        self.fid.get_slice(self.start + i, size)
+    }
+
+    fn has_fast_get_slice(&self) -> bool {
+        self.fid.has_fast_get_slice()
+    }
+}
+
+impl<'i, 'a, T: FID> IntoIterator for &'i FidSlice<'a, T> {
+    type Item = bool;
+
+    type IntoIter = FidBitIter<'i, FidSlice<'a, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        FidBitIter::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BitVector, FID};
+    use rand::{Rng, SeedableRng, StdRng};
+
+    #[test]
+    fn slice() {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        for &p in &[0.01, 0.5, 0.99] {
+            let n = 5_000;
+            let bits: Vec<bool> = (0..n).map(|_| rng.gen_bool(p)).collect();
+            let bv = BitVector::from(bits.as_slice());
+
+            for _ in 0..10 {
+                let start = rng.gen_range(0, n);
+                let end = rng.gen_range(start, n + 1);
+                let window = &bits[start as usize..end as usize];
+                let expected = BitVector::from(window);
+                let slice = bv.slice(start..end);
+
+                let len = end - start;
+                assert_eq!(slice.len(), len);
+                for i in 0..=len {
+                    assert_eq!(slice.rank1(i), expected.rank1(i));
+                    assert_eq!(slice.rank0(i), expected.rank0(i));
+                }
+                let ones = expected.rank1(len);
+                for r in 0..=ones {
+                    assert_eq!(slice.select1(r), expected.select1(r));
+                }
+                for r in 0..=len - ones {
+                    assert_eq!(slice.select0(r), expected.select0(r));
+                }
+                assert_eq!(slice.to_vec(), window);
+                assert_eq!(slice.to_words(), expected.to_words());
+                assert!(slice.iter_runs().eq(expected.iter_runs()));
+            }
+        }
+    }
+}
@@ -0,0 +1,545 @@
+use crate::bit_array::BitArray;
+use crate::bit_vector::BitVector;
+use crate::coding::ComboTable;
+use crate::fid::FID;
+use crate::fid_iter::FidBitIter;
+use crate::util::mask_u64;
+
+const CHUNK_WIDTH: u64 = 1 << 16;
+const CHUNK_WORDS: usize = (CHUNK_WIDTH / u64::BITS as u64) as usize;
+
+/// Representation of a single chunk of a [`HybridBitmap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind {
+    /// Sorted positions of 1s, 16 bits per 1.
+    Array,
+    /// Uncompressed bits, with 16 more bits per word for the rank.
+    Bitmap,
+    /// Runs of 1s, 48 bits per run.
+    Runs,
+    /// Compressed [`BitVector`].
+    Compressed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemDbg, mem_dbg::MemSize))]
+enum Container {
+    Array(Vec<u16>),
+    Bitmap {
+        bits: BitArray,
+        /// Number of 1s before each word.
+        ranks: Vec<u16>,
+    },
+    Runs {
+        /// Runs as `(first, last)` positions, both inclusive.
+        runs: Vec<(u16, u16)>,
+        /// Number of 1s before each run.
+        ranks: Vec<u16>,
+    },
+    Compressed(BitVector),
+}
+
+impl Container {
+    /// Picks the smallest representation for the bits in `words`.
+    fn from_words(words: &[u64], len: u64) -> Self {
+        let mut array = Vec::new();
+        let mut runs: Vec<(u16, u16)> = Vec::new();
+        for (k, &word) in words.iter().enumerate() {
+            let mut bits = word;
+            while bits != 0 {
+                let p = bits.trailing_zeros();
+                let pos = (k as u64 * u64::BITS as u64 + p as u64) as u16;
+                array.push(pos);
+                match runs.last_mut() {
+                    Some((_, last)) if *last + 1 == pos => *last = pos,
+                    _ => runs.push((pos, pos)),
+                }
+                bits &= bits - 1;
+            }
+        }
+
+        let sizes = [
+            array.len() as u64 * u16::BITS as u64,
+            words.len() as u64 * (u64::BITS + u16::BITS) as u64,
+            runs.len() as u64 * 3 * u16::BITS as u64,
+            BitVector::estimate_encoded_bits(words, len),
+        ];
+        let kind = (0..sizes.len()).min_by_key(|&k| sizes[k]).unwrap();
+        match kind {
+            0 => Container::Array(array),
+            1 => {
+                // The total may not fit in a `u16`, but is not stored.
+                let mut rank = 0u32;
+                let ranks = words
+                    .iter()
+                    .map(|word| {
+                        let r = rank as u16;
+                        rank += word.count_ones();
+                        r
+                    })
+                    .collect();
+                let bits = BitArray::from_blocks(words.to_vec());
+                Container::Bitmap { bits, ranks }
+            }
+            2 => {
+                let mut rank = 0u32;
+                let ranks = runs
+                    .iter()
+                    .map(|&(first, last)| {
+                        let r = rank as u16;
+                        rank += (last - first) as u32 + 1;
+                        r
+                    })
+                    .collect();
+                Container::Runs { runs, ranks }
+            }
+            _ => {
+                let mut compressed = BitVector::from_words(words, len);
+                compressed.shrink_to_fit();
+                Container::Compressed(compressed)
+            }
+        }
+    }
+
+    fn kind(&self) -> ContainerKind {
+        match self {
+            Container::Array(_) => ContainerKind::Array,
+            Container::Bitmap { .. } => ContainerKind::Bitmap,
+            Container::Runs { .. } => ContainerKind::Runs,
+            Container::Compressed(_) => ContainerKind::Compressed,
+        }
+    }
+
+    fn rank1(&self, i: u64) -> u64 {
+        match self {
+            Container::Array(array) => array.partition_point(|&p| (p as u64) < i) as u64,
+            Container::Bitmap { bits, ranks } => {
+                let k = i / u64::BITS as u64;
+                let p = i % u64::BITS as u64;
+                if k as usize == ranks.len() {
+                    // `i` is the end of a full chunk.
+                    let last = bits.get_word(k - 1, 64).count_ones();
+                    return ranks[k as usize - 1] as u64 + last as u64;
+                }
+                let part = bits.get_word(k, 64) & mask_u64(p);
+                ranks[k as usize] as u64 + part.count_ones() as u64
+            }
+            Container::Runs { runs, ranks } => {
+                let k = runs.partition_point(|&(first, _)| (first as u64) < i);
+                if k == 0 {
+                    return 0;
+                }
+                let (first, last) = runs[k - 1];
+                ranks[k - 1] as u64 + (last as u64 + 1).min(i) - first as u64
+            }
+            Container::Compressed(bv) => bv.rank1(i),
+        }
+    }
+
+    fn select1(&self, r: u64) -> u64 {
+        match self {
+            Container::Array(array) => array[r as usize] as u64,
+            Container::Bitmap { bits, ranks } => {
+                let k = ranks.partition_point(|&rank| rank as u64 <= r) - 1;
+                let word = bits.get_word(k as u64, 64);
+                let r = (r - ranks[k] as u64) as u32;
+                k as u64 * u64::BITS as u64 + ComboTable::select0_raw(!word, r) as u64
+            }
+            Container::Runs { runs, ranks } => {
+                let k = ranks.partition_point(|&rank| rank as u64 <= r) - 1;
+                runs[k].0 as u64 + r - ranks[k] as u64
+            }
+            Container::Compressed(bv) => bv.select1(r),
+        }
+    }
+
+    fn select0(&self, r: u64) -> u64 {
+        match self {
+            Container::Array(array) => {
+                // The `k`-th 1 is preceded by `array[k] - k` zeros.
+                let (mut s, mut e) = (0, array.len());
+                while s < e {
+                    let m = (s + e) / 2;
+                    if array[m] as u64 - m as u64 <= r {
+                        s = m + 1;
+                    } else {
+                        e = m;
+                    }
+                }
+                r + s as u64
+            }
+            Container::Bitmap { bits, ranks } => {
+                // Word `k` is preceded by `64 * k - ranks[k]` zeros.
+                let zeros = |k: usize| k as u64 * u64::BITS as u64 - ranks[k] as u64;
+                let k = partition_point(ranks.len(), |k| zeros(k) <= r) - 1;
+                let word = bits.get_word(k as u64, 64);
+                let r = (r - zeros(k)) as u32;
+                k as u64 * u64::BITS as u64 + ComboTable::select0_raw(word, r) as u64
+            }
+            Container::Runs { runs, ranks } => {
+                // Run `k` is preceded by `first - ranks[k]` zeros.
+                let k = partition_point(runs.len(), |k| {
+                    runs[k].0 as u64 - ranks[k] as u64 <= r
+                });
+                if k == 0 {
+                    return r;
+                }
+                let (first, last) = runs[k - 1];
+                r + ranks[k - 1] as u64 + last as u64 - first as u64 + 1
+            }
+            Container::Compressed(bv) => bv.select0(r),
+        }
+    }
+
+    fn get_slice(&self, i: u64, size: u64) -> u64 {
+        let end = i + size;
+        match self {
+            Container::Array(array) => {
+                let start = array.partition_point(|&p| (p as u64) < i);
+                array[start..]
+                    .iter()
+                    .take_while(|&&p| (p as u64) < end)
+                    .fold(0, |bits, &p| bits | 1 << (p as u64 - i))
+            }
+            Container::Bitmap { bits, .. } => bits.get_slice(i, size),
+            Container::Runs { runs, .. } => {
+                let start = runs.partition_point(|&(_, last)| (last as u64) < i);
+                runs[start..]
+                    .iter()
+                    .take_while(|&&(first, _)| (first as u64) < end)
+                    .fold(0, |bits, &(first, last)| {
+                        let lo = (first as u64).max(i);
+                        let hi = (last as u64 + 1).min(end);
+                        bits | mask_u64(hi - lo) << (lo - i)
+                    })
+            }
+            Container::Compressed(bv) => bv.get_slice(i, size),
+        }
+    }
+}
+
+/// Returns the first `k` in `[0..len)` for which `pred` is false, or `len`.
+fn partition_point<F: Fn(usize) -> bool>(len: usize, pred: F) -> usize {
+    let (mut lo, mut hi) = (0, len);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+/// A bitmap that stores each chunk of `2^16` bits in its smallest representation.
+///
+/// Like Roaring bitmaps, every chunk is stored as either a sorted array of positions,
+/// an uncompressed [`BitArray`], a list of runs or a compressed [`BitVector`],
+/// whichever is smallest. This handles bitmaps mixing very sparse and very dense
+/// regions. The number of 1s before each chunk is stored to answer global
+/// `rank` and `select` queries.
+///
+/// # Examples
+///
+/// ```
+/// # use fid::{FID, HybridBitmap};
+/// let hb: HybridBitmap = (0..1_000_000u64).map(|i| i % 1000 == 0 || i > 900_000).collect();
+/// assert_eq!(hb.rank1(10_000), 10);
+/// assert_eq!(hb.select1(10), 10_000);
+/// assert_eq!(hb.select1(901), 900_001);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemDbg, mem_dbg::MemSize))]
+pub struct HybridBitmap {
+    /// Length of the bitmap (number of bits).
+    len: u64,
+    /// Number of 1s.
+    ones: u64,
+    /// Number of 1s before each chunk.
+    ranks: Vec<u64>,
+    containers: Vec<Container>,
+}
+
+impl HybridBitmap {
+    /// Constructs a new, empty [`HybridBitmap`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs a new [`HybridBitmap`] with the bits of any [`FID`].
+    pub fn from_fid<T: FID>(fid: &T) -> Self {
+        let mut builder = ChunkBuilder::default();
+        let len = fid.len();
+        for (k, word) in fid.iter_words().enumerate() {
+            let size = (len - k as u64 * u64::BITS as u64).min(u64::BITS as u64);
+            builder.push_word(word, size);
+        }
+        builder.build()
+    }
+
+    /// Returns the number of chunks.
+    pub fn chunk_len(&self) -> usize {
+        self.containers.len()
+    }
+
+    /// Returns the representation of the `i`-th chunk.
+    pub fn container_kind(&self, i: usize) -> ContainerKind {
+        self.containers[i].kind()
+    }
+
+    pub fn iter(&self) -> FidBitIter<'_, Self> {
+        FidBitIter::new(self)
+    }
+
+    pub fn to_vec(&self) -> Vec<bool> {
+        self.iter().collect()
+    }
+
+    /// Returns the number of bits in the `c`-th chunk.
+    fn get_chunk_len(&self, c: usize) -> u64 {
+        (self.len - c as u64 * CHUNK_WIDTH).min(CHUNK_WIDTH)
+    }
+}
+
+#[derive(Default)]
+struct ChunkBuilder {
+    bitmap: HybridBitmap,
+    words: Vec<u64>,
+    len: u64,
+}
+
+impl ChunkBuilder {
+    /// Appends `size` bits from `word`, which must be aligned to a word.
+    fn push_word(&mut self, word: u64, size: u64) {
+        debug_assert!(self.len.is_multiple_of(u64::BITS as u64));
+        self.words.push(word & mask_u64(size));
+        self.len += size;
+        if self.words.len() == CHUNK_WORDS {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.len == 0 {
+            return;
+        }
+        let container = Container::from_words(&self.words, self.len);
+        let bitmap = &mut self.bitmap;
+        bitmap.ranks.push(bitmap.ones);
+        bitmap.ones += container.rank1(self.len);
+        bitmap.len += self.len;
+        bitmap.containers.push(container);
+        self.words.clear();
+        self.len = 0;
+    }
+
+    fn build(mut self) -> HybridBitmap {
+        self.flush();
+        self.bitmap
+    }
+}
+
+impl FID for HybridBitmap {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn rank1(&self, i: u64) -> u64 {
+        if self.len <= i {
+            return self.ones;
+        }
+        let c = (i / CHUNK_WIDTH) as usize;
+        self.ranks[c] + self.containers[c].rank1(i % CHUNK_WIDTH)
+    }
+
+    fn select(&self, b: bool, r: u64) -> u64 {
+        if b {
+            self.select1(r)
+        } else {
+            self.select0(r)
+        }
+    }
+
+    fn select0(&self, r: u64) -> u64 {
+        if self.len - self.ones <= r {
+            return self.len;
+        }
+        let zeros = |c: usize| c as u64 * CHUNK_WIDTH - self.ranks[c];
+        let (mut s, mut e) = (0, self.ranks.len());
+        while e - s > 1 {
+            let m = (s + e) / 2;
+            if zeros(m) <= r {
+                s = m;
+            } else {
+                e = m;
+            }
+        }
+        s as u64 * CHUNK_WIDTH + self.containers[s].select0(r - zeros(s))
+    }
+
+    fn select1(&self, r: u64) -> u64 {
+        if self.ones <= r {
+            return self.len;
+        }
+        let c = self.ranks.partition_point(|&rank| rank <= r) - 1;
+        c as u64 * CHUNK_WIDTH + self.containers[c].select1(r - self.ranks[c])
+    }
+
+    fn get(&self, i: u64) -> bool {
+        self.get_slice(i, 1) != 0
+    }
+
+    fn get_slice(&self, i: u64, size: u64) -> u64 {
+        debug_assert!(size <= 64);
+
+        let slice_end = i + size;
+        assert!(slice_end <= self.len);
+        if size == 0 {
+            return 0;
+        }
+
+        let c = (i / CHUNK_WIDTH) as usize;
+        let p = i % CHUNK_WIDTH;
+        let lo_size = size.min(self.get_chunk_len(c) - p);
+        let lo_bits = self.containers[c].get_slice(p, lo_size);
+        if lo_size == size {
+            return lo_bits;
+        }
+        let hi_bits = self.containers[c + 1].get_slice(0, size - lo_size);
+        lo_bits | hi_bits << lo_size
+    }
+
+    fn has_fast_get_slice(&self) -> bool {
+        true
+    }
+}
+
+impl<'i> IntoIterator for &'i HybridBitmap {
+    type Item = bool;
+
+    type IntoIter = FidBitIter<'i, HybridBitmap>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        FidBitIter::new(self)
+    }
+}
+
+impl From<&[bool]> for HybridBitmap {
+    fn from(value: &[bool]) -> Self {
+        value.iter().copied().collect()
+    }
+}
+
+impl FromIterator<bool> for HybridBitmap {
+    fn from_iter<T: IntoIterator<Item = bool>>(iter: T) -> Self {
+        let mut builder = ChunkBuilder::default();
+        let mut word = 0;
+        let mut size = 0;
+        for b in iter {
+            word |= (b as u64) << size;
+            size += 1;
+            if size == u64::BITS as u64 {
+                builder.push_word(word, size);
+                word = 0;
+                size = 0;
+            }
+        }
+        if size != 0 {
+            builder.push_word(word, size);
+        }
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, StdRng};
+
+    /// Generates chunks with different densities and clustering.
+    fn gen_mixed() -> Vec<bool> {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        let mut vec = Vec::new();
+        for &p in &[0.001, 0.5, 0.1, 0.0] {
+            for _ in 0..CHUNK_WIDTH {
+                vec.push(rng.gen_bool(p));
+            }
+        }
+        let mut b = false;
+        while (vec.len() as u64) < CHUNK_WIDTH * 5 {
+            let len = rng
+                .gen_range(1, 1000)
+                .min(CHUNK_WIDTH as usize * 5 - vec.len());
+            vec.extend(std::iter::repeat_n(b, len));
+            b = !b;
+        }
+        for _ in 0..1000 {
+            vec.push(rng.gen_bool(0.5));
+        }
+        vec
+    }
+
+    #[test]
+    fn container_kinds() {
+        let vec = gen_mixed();
+        let hb = HybridBitmap::from(vec.as_slice());
+        let kinds: Vec<_> = (0..hb.chunk_len()).map(|c| hb.container_kind(c)).collect();
+        assert_eq!(
+            kinds,
+            [
+                ContainerKind::Array,
+                ContainerKind::Bitmap,
+                ContainerKind::Compressed,
+                ContainerKind::Array,
+                ContainerKind::Runs,
+                ContainerKind::Bitmap,
+            ]
+        );
+    }
+
+    #[test]
+    fn rank_select() {
+        let vec = gen_mixed();
+        let hb = HybridBitmap::from(vec.as_slice());
+        let bv = BitVector::from(vec.as_slice());
+        let n = vec.len() as u64;
+        assert_eq!(hb.len(), n);
+
+        for i in (0..=n).step_by(7) {
+            assert_eq!(hb.rank1(i), bv.rank1(i));
+        }
+        let ones = bv.rank1(n);
+        for r in (0..ones).step_by(5) {
+            assert_eq!(hb.select1(r), bv.select1(r));
+        }
+        for r in (0..n - ones).step_by(5) {
+            assert_eq!(hb.select0(r), bv.select0(r));
+        }
+        assert_eq!(hb.select1(ones), n);
+        assert_eq!(hb.select0(n - ones), n);
+    }
+
+    #[test]
+    fn get() {
+        let vec = gen_mixed();
+        let hb = HybridBitmap::from(vec.as_slice());
+        let bv = BitVector::from(vec.as_slice());
+        assert_eq!(hb.to_vec(), vec);
+        for i in (0..hb.len() - 64).step_by(13) {
+            assert_eq!(hb.get_slice(i, 64), bv.get_slice(i, 64));
+        }
+        assert_eq!(HybridBitmap::from_fid(&bv), hb);
+    }
+
+    #[test]
+    fn empty() {
+        let hb = HybridBitmap::new();
+        assert_eq!(hb.len(), 0);
+        assert_eq!(hb.rank1(0), 0);
+        assert_eq!(hb.select1(0), 0);
+        assert_eq!(hb.select0(0), 0);
+    }
+}
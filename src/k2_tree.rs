@@ -0,0 +1,309 @@
+use crate::bit_array::BitArray;
+use crate::bit_vector::BitVector;
+use crate::fid::FID;
+use std::ops::Range;
+
+/// A k²-tree: a compressed adjacency matrix for sparse graphs.
+///
+/// The matrix is padded to `k^h × k^h` and recursively split into `k²` submatrices,
+/// each represented by a bit that tells whether it contains an edge. Only non-empty
+/// submatrices are split further. The bits of all levels are concatenated in
+/// breadth-first order, so that the children of the 1 at position `x` start at
+/// `rank1(x + 1) * k²`. The internal levels are stored in a [`BitVector`] and
+/// the last level (single cells) in a [`BitArray`].
+///
+/// Clustered graphs such as web graphs take a few bits per edge.
+/// Checking an edge takes `h` steps, and listing the successors or predecessors
+/// of a node visits at most `k` children per non-empty submatrix on its row or column.
+///
+/// # Examples
+///
+/// ```
+/// # use fid::K2Tree;
+/// let tree = K2Tree::new(6, &[(0, 1), (1, 2), (1, 5), (4, 2)]);
+/// assert!(tree.has_edge(1, 5));
+/// assert!(!tree.has_edge(5, 1));
+/// assert_eq!(tree.successors(1), [2, 5]);
+/// assert_eq!(tree.predecessors(2), [1, 4]);
+/// assert_eq!(tree.range(0..2, 2..6), [(1, 2), (1, 5)]);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemDbg, mem_dbg::MemSize))]
+pub struct K2Tree {
+    /// Number of nodes.
+    node_count: u64,
+    /// Number of edges.
+    edge_count: u64,
+    k: u64,
+    /// Size of the padded matrix (`k^h`).
+    size: u64,
+    /// Bits of the internal levels.
+    tree: BitVector,
+    /// Bits of the last level.
+    leaves: BitArray,
+}
+
+impl K2Tree {
+    /// Constructs a new [`K2Tree`] with `k = 2` over `node_count` nodes from a list of edges.
+    ///
+    /// # Panics
+    /// * An edge is out of bounds.
+    pub fn new(node_count: u64, edges: &[(u64, u64)]) -> Self {
+        Self::with_arity(2, node_count, edges)
+    }
+
+    /// Constructs a new [`K2Tree`] splitting each submatrix into `k²` parts.
+    ///
+    /// Duplicate edges are ignored.
+    ///
+    /// # Panics
+    /// * `k` is less than 2.
+    /// * An edge is out of bounds.
+    pub fn with_arity(k: u64, node_count: u64, edges: &[(u64, u64)]) -> Self {
+        assert!(k >= 2, "k must be at least 2");
+        for &(u, v) in edges {
+            assert!(
+                u < node_count && v < node_count,
+                "edge ({u}, {v}) is out of bounds"
+            );
+        }
+        let mut edges = edges.to_vec();
+        edges.sort_unstable();
+        edges.dedup();
+        let edge_count = edges.len() as u64;
+
+        let mut size = k;
+        while size < node_count {
+            size *= k;
+        }
+
+        let mut tree = BitVector::new();
+        let mut leaves = BitArray::new();
+        let mut leaf_len = 0;
+        let mut level = vec![edges];
+        let mut child_size = size;
+        while child_size > 1 {
+            child_size /= k;
+            let mut next = Vec::new();
+            for node_edges in level {
+                let mut children = vec![Vec::new(); (k * k) as usize];
+                for (u, v) in node_edges {
+                    let child = (u / child_size) * k + v / child_size;
+                    children[child as usize].push((u % child_size, v % child_size));
+                }
+                for child_edges in children {
+                    let b = !child_edges.is_empty();
+                    if child_size == 1 {
+                        leaves.set_bit(leaf_len, b);
+                        leaf_len += 1;
+                    } else {
+                        tree.push(b);
+                        if b {
+                            next.push(child_edges);
+                        }
+                    }
+                }
+            }
+            level = next;
+        }
+        tree.shrink_to_fit();
+        leaves.shrink_to_fit();
+
+        K2Tree {
+            node_count,
+            edge_count,
+            k,
+            size,
+            tree,
+            leaves,
+        }
+    }
+
+    /// Returns the number of nodes.
+    pub fn node_count(&self) -> u64 {
+        self.node_count
+    }
+
+    /// Returns the number of edges.
+    pub fn edge_count(&self) -> u64 {
+        self.edge_count
+    }
+
+    /// Returns the number of parts each side of a submatrix is split into.
+    pub fn arity(&self) -> u64 {
+        self.k
+    }
+
+    /// Returns true if there is an edge from `u` to `v`.
+    pub fn has_edge(&self, u: u64, v: u64) -> bool {
+        if u >= self.node_count || v >= self.node_count {
+            return false;
+        }
+        let k = self.k;
+        let (mut u, mut v) = (u, v);
+        let mut offset = 0;
+        let mut child_size = self.size;
+        loop {
+            child_size /= k;
+            let pos = offset + (u / child_size) * k + v / child_size;
+            if child_size == 1 {
+                return self.leaves.get_bit(pos - self.tree.len());
+            }
+            if !self.tree.get(pos) {
+                return false;
+            }
+            offset = self.children(pos);
+            u %= child_size;
+            v %= child_size;
+        }
+    }
+
+    /// Returns the successors of `u` in increasing order.
+    pub fn successors(&self, u: u64) -> Vec<u64> {
+        let mut out = Vec::new();
+        if u < self.node_count {
+            self.visit(u..u + 1, 0..self.node_count, &mut |_, v| out.push(v));
+        }
+        out
+    }
+
+    /// Returns the predecessors of `v` in increasing order.
+    pub fn predecessors(&self, v: u64) -> Vec<u64> {
+        let mut out = Vec::new();
+        if v < self.node_count {
+            self.visit(0..self.node_count, v..v + 1, &mut |u, _| out.push(u));
+        }
+        out
+    }
+
+    /// Returns the edges from `rows` to `cols`, sorted.
+    pub fn range(&self, rows: Range<u64>, cols: Range<u64>) -> Vec<(u64, u64)> {
+        let mut out = Vec::new();
+        self.visit(rows, cols, &mut |u, v| out.push((u, v)));
+        out.sort_unstable();
+        out
+    }
+
+    /// Returns all edges, sorted.
+    pub fn edges(&self) -> Vec<(u64, u64)> {
+        self.range(0..self.node_count, 0..self.node_count)
+    }
+
+    /// Returns the position of the children of the 1 at `pos` in `tree`.
+    fn children(&self, pos: u64) -> u64 {
+        self.tree.rank1(pos + 1) * self.k * self.k
+    }
+
+    /// Calls `f` for every edge in the submatrix of `rows` and `cols`.
+    fn visit<F: FnMut(u64, u64)>(&self, rows: Range<u64>, cols: Range<u64>, f: &mut F) {
+        let rows = rows.start..rows.end.min(self.node_count);
+        let cols = cols.start..cols.end.min(self.node_count);
+        if !rows.is_empty() && !cols.is_empty() {
+            self.visit_node(0, self.size, (0, 0), &rows, &cols, f);
+        }
+    }
+
+    /// Visits the children at `offset` of the submatrix of `size` at `origin`.
+    fn visit_node<F: FnMut(u64, u64)>(
+        &self,
+        offset: u64,
+        size: u64,
+        origin: (u64, u64),
+        rows: &Range<u64>,
+        cols: &Range<u64>,
+        f: &mut F,
+    ) {
+        let k = self.k;
+        let child_size = size / k;
+        // Children overlapping the query, relative to the origin.
+        let overlap = |range: &Range<u64>, start: u64| {
+            let first = range.start.saturating_sub(start) / child_size;
+            let last = ((range.end - 1).saturating_sub(start) / child_size).min(k - 1);
+            first..last + 1
+        };
+        for i in overlap(rows, origin.0) {
+            let u = origin.0 + i * child_size;
+            if u >= rows.end {
+                break;
+            }
+            for j in overlap(cols, origin.1) {
+                let v = origin.1 + j * child_size;
+                if v >= cols.end {
+                    break;
+                }
+                let pos = offset + i * k + j;
+                if child_size == 1 {
+                    if self.leaves.get_bit(pos - self.tree.len()) {
+                        f(u, v);
+                    }
+                } else if self.tree.get(pos) {
+                    let children = self.children(pos);
+                    self.visit_node(children, child_size, (u, v), rows, cols, f);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, StdRng};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn queries() {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        for &(k, n, m) in &[
+            (2, 1, 1),
+            (2, 7, 10),
+            (2, 64, 300),
+            (3, 100, 500),
+            (4, 1000, 3000),
+        ] {
+            let edges: Vec<(u64, u64)> = (0..m)
+                .map(|_| (rng.gen_range(0, n), rng.gen_range(0, n)))
+                .collect();
+            let set: BTreeSet<(u64, u64)> = edges.iter().copied().collect();
+            let tree = K2Tree::with_arity(k, n, &edges);
+            assert_eq!(tree.edge_count(), set.len() as u64);
+            assert_eq!(tree.edges(), set.iter().copied().collect::<Vec<_>>());
+
+            for _ in 0..500 {
+                let (u, v) = (rng.gen_range(0, n), rng.gen_range(0, n));
+                assert_eq!(tree.has_edge(u, v), set.contains(&(u, v)));
+            }
+            for u in 0..n.min(100) {
+                let succ: Vec<u64> = set.range((u, 0)..(u + 1, 0)).map(|e| e.1).collect();
+                assert_eq!(tree.successors(u), succ, "successors({u})");
+                let pred: Vec<u64> = set.iter().filter(|e| e.1 == u).map(|e| e.0).collect();
+                assert_eq!(tree.predecessors(u), pred, "predecessors({u})");
+            }
+            for _ in 0..50 {
+                let (a, b) = (rng.gen_range(0, n + 1), rng.gen_range(0, n + 1));
+                let (c, d) = (rng.gen_range(0, n + 1), rng.gen_range(0, n + 1));
+                let (rows, cols) = (a.min(b)..a.max(b), c.min(d)..c.max(d));
+                let expected: Vec<(u64, u64)> = set
+                    .iter()
+                    .copied()
+                    .filter(|(u, v)| rows.contains(u) && cols.contains(v))
+                    .collect();
+                assert_eq!(tree.range(rows, cols), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let tree = K2Tree::new(10, &[]);
+        assert_eq!(tree.edge_count(), 0);
+        assert!(!tree.has_edge(3, 4));
+        assert!(tree.successors(3).is_empty());
+        assert!(tree.edges().is_empty());
+
+        let tree = K2Tree::new(0, &[]);
+        assert!(!tree.has_edge(0, 0));
+        assert!(tree.edges().is_empty());
+    }
+}
@@ -33,14 +33,52 @@
 //! [2] rsdic by Daisuke Okanohara.
 //! [https://github.com/hillbig/rsdic](https://github.com/hillbig/rsdic)
 
+mod append_only;
 mod bit_array;
+mod bit_io;
 mod bit_vector;
 mod coding;
+mod complement;
+mod document_array;
+mod elias_fano;
 mod fid;
 mod fid_iter;
+mod fid_slice;
+mod hybrid_bitmap;
+mod k2_tree;
+mod louds_trie;
+mod monotone_sequence;
+mod naive_fid;
+mod pattern_index;
+mod rle_bit_vector;
+mod rmq;
+mod small_alphabet;
+mod space_report;
+mod succinct_csr;
 mod util;
 
+#[cfg(test)]
+mod differential_tests;
+#[cfg(test)]
+mod property_tests;
+
+pub use crate::append_only::{BitVectorReader, BitVectorSnapshot, BitVectorWriter};
 pub use crate::bit_array::BitArray;
-pub use crate::bit_vector::BitVector;
+pub use crate::bit_io::{BitReader, BitWriter, Code};
+pub use crate::bit_vector::{BitVector, BitVectorWordIter};
+pub use crate::complement::Complement;
+pub use crate::document_array::DocumentArray;
 pub use crate::fid::FID;
-pub use crate::fid_iter::FidBitIter;
\ No newline at end of file
+pub use crate::fid_iter::{FidBitIter, FidRunIter, FidWordIter};
+pub use crate::fid_slice::FidSlice;
+pub use crate::hybrid_bitmap::{ContainerKind, HybridBitmap};
+pub use crate::k2_tree::K2Tree;
+pub use crate::louds_trie::LoudsTrie;
+pub use crate::monotone_sequence::{MonotoneIter, MonotoneSequence};
+pub use crate::naive_fid::NaiveFid;
+pub use crate::pattern_index::{FidWords, PatternIndex, ReadWords};
+pub use crate::rle_bit_vector::RleBitVector;
+pub use crate::rmq::Rmq;
+pub use crate::small_alphabet::SmallAlphabetSequence;
+pub use crate::space_report::SpaceReport;
+pub use crate::succinct_csr::{CsrNeighbors, SuccinctCsr};
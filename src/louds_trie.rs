@@ -0,0 +1,333 @@
+use crate::bit_vector::BitVector;
+use crate::fid::FID;
+use crate::monotone_sequence::MonotoneSequence;
+use std::collections::VecDeque;
+use std::ops::Range;
+
+/// A static string dictionary on a path-compressed trie with LOUDS navigation.
+///
+/// The tree shape is stored with LOUDS (level-order unary degree sequence) in a
+/// [`BitVector`]: after a leading `10`, every node in breadth-first order writes a 1
+/// for each child followed by a 0. The `i`-th 1 is then node `i`, and the children
+/// of node `i` follow the `i`-th 0. Chains of nodes with a single child are merged
+/// into one edge: the first byte of every edge is stored per node for the search
+/// over children, and the rest in a byte array delimited by a [`MonotoneSequence`].
+///
+/// Keys get ids in `0..len` in breadth-first order of their nodes.
+///
+/// # Examples
+///
+/// ```
+/// # use fid::LoudsTrie;
+/// let trie = LoudsTrie::new(&["app", "apple", "apply", "banana", "band"]);
+/// let id = trie.lookup("apple").unwrap();
+/// assert_eq!(trie.access(id), "apple");
+/// assert_eq!(trie.lookup("appl"), None);
+///
+/// let keys: Vec<String> = trie.predictive_search("appl").map(|id| trie.access(id)).collect();
+/// assert_eq!(keys, ["apple", "apply"]);
+///
+/// let prefixes: Vec<(String, usize)> = trie
+///     .common_prefix_search("applesauce")
+///     .map(|(id, len)| (trie.access(id), len))
+///     .collect();
+/// assert_eq!(prefixes, [("app".to_string(), 3), ("apple".to_string(), 5)]);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemDbg, mem_dbg::MemSize))]
+pub struct LoudsTrie {
+    /// Number of keys.
+    len: u64,
+    louds: BitVector,
+    /// Whether each node ends a key.
+    terminals: BitVector,
+    /// First byte of the edge to each node (0 for the root).
+    first_bytes: Vec<u8>,
+    /// Remaining bytes of the edges, concatenated.
+    tails: Vec<u8>,
+    /// Start of the tail of each node in `tails`.
+    tail_offsets: MonotoneSequence,
+}
+
+impl LoudsTrie {
+    /// Constructs a new [`LoudsTrie`] containing `keys`. Duplicates are ignored.
+    pub fn new<K: AsRef<str>>(keys: &[K]) -> Self {
+        let mut keys: Vec<&[u8]> = keys.iter().map(|key| key.as_ref().as_bytes()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+
+        let mut louds = BitVector::from_words(&[0b01], 2);
+        let mut terminals = BitVector::new();
+        let mut first_bytes = vec![0];
+        let mut tails = Vec::new();
+        let mut tail_lengths = vec![0];
+
+        // Nodes in breadth-first order, as the keys below them and their depth.
+        let mut queue = VecDeque::from([(0..keys.len(), 0)]);
+        while let Some((range, depth)) = queue.pop_front() {
+            let mut lo = range.start;
+            let terminal = lo < range.end && keys[lo].len() == depth;
+            terminals.push(terminal);
+            lo += terminal as usize;
+
+            while lo < range.end {
+                let b = keys[lo][depth];
+                let hi = lo + keys[lo..range.end].partition_point(|key| key[depth] == b);
+                // Keys are sorted, so the first and last share the prefix of all.
+                let (first, last) = (keys[lo], keys[hi - 1]);
+                let common = first[depth..]
+                    .iter()
+                    .zip(&last[depth..])
+                    .take_while(|(x, y)| x == y)
+                    .count();
+
+                louds.push(true);
+                first_bytes.push(b);
+                tails.extend_from_slice(&first[depth + 1..depth + common]);
+                tail_lengths.push(common as u64 - 1);
+                queue.push_back((lo..hi, depth + common));
+                lo = hi;
+            }
+            louds.push(false);
+        }
+        louds.shrink_to_fit();
+        terminals.shrink_to_fit();
+        first_bytes.shrink_to_fit();
+        tails.shrink_to_fit();
+
+        LoudsTrie {
+            len: keys.len() as u64,
+            louds,
+            terminals,
+            first_bytes,
+            tails,
+            tail_offsets: MonotoneSequence::from_lengths(tail_lengths),
+        }
+    }
+
+    /// Returns the number of keys.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if there are no keys.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of nodes.
+    pub fn node_count(&self) -> u64 {
+        self.terminals.len()
+    }
+
+    /// Returns the id of `key`, if it is in the dictionary.
+    pub fn lookup(&self, key: &str) -> Option<u64> {
+        let key = key.as_bytes();
+        let mut node = 0;
+        let mut depth = 0;
+        while depth < key.len() {
+            node = self.child(node, key[depth])?;
+            let tail = self.tail(node);
+            if !key[depth + 1..].starts_with(tail) {
+                return None;
+            }
+            depth += 1 + tail.len();
+        }
+        self.key_id(node)
+    }
+
+    /// Returns the key with `id`.
+    ///
+    /// # Panics
+    /// * `id` is out of bounds.
+    pub fn access(&self, id: u64) -> String {
+        assert!(id < self.len, "id {id} is out of bounds");
+        let mut node = self.terminals.select1(id);
+        let mut key = Vec::new();
+        while node != 0 {
+            key.extend(self.tail(node).iter().rev());
+            key.push(self.first_bytes[node as usize]);
+            node = self.parent(node);
+        }
+        key.reverse();
+        String::from_utf8(key).expect("keys are built from strings")
+    }
+
+    /// Returns the ids of the keys starting with `prefix`, in lexicographic order of the keys.
+    pub fn predictive_search(&self, prefix: &str) -> impl Iterator<Item = u64> + '_ {
+        let prefix = prefix.as_bytes();
+        let mut stack = Vec::new();
+        let mut node = Some(0);
+        let mut depth = 0;
+        while depth < prefix.len() {
+            node = node.and_then(|node| self.child(node, prefix[depth]));
+            let Some(child) = node else { break };
+            let tail = self.tail(child);
+            let rest = &prefix[depth + 1..];
+            // The prefix may end inside the edge.
+            if !(rest.starts_with(tail) || tail.starts_with(rest)) {
+                node = None;
+                break;
+            }
+            depth += 1 + tail.len();
+        }
+        stack.extend(node);
+
+        // Depth-first traversal, visiting children in order of their labels.
+        std::iter::from_fn(move || {
+            while let Some(node) = stack.pop() {
+                let children = self.children(node);
+                stack.extend(children.rev());
+                if let Some(id) = self.key_id(node) {
+                    return Some(id);
+                }
+            }
+            None
+        })
+    }
+
+    /// Returns the ids and lengths of the keys that are prefixes of `text`, shortest first.
+    pub fn common_prefix_search<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = (u64, usize)> + 'a {
+        let text = text.as_bytes();
+        let mut node = Some(0);
+        let mut depth = 0;
+        std::iter::from_fn(move || {
+            while let Some(current) = node {
+                let id = self.key_id(current);
+                let end = depth;
+                node = text.get(depth).and_then(|&b| {
+                    let child = self.child(current, b)?;
+                    let tail = self.tail(child);
+                    text[depth + 1..].starts_with(tail).then(|| {
+                        depth += 1 + tail.len();
+                        child
+                    })
+                });
+                if let Some(id) = id {
+                    return Some((id, end));
+                }
+            }
+            None
+        })
+    }
+
+    /// Returns the id of the key ending at `node`.
+    fn key_id(&self, node: u64) -> Option<u64> {
+        self.terminals.get(node).then(|| self.terminals.rank1(node))
+    }
+
+    /// Returns the nodes of the children of `node`.
+    fn children(&self, node: u64) -> Range<u64> {
+        let start = self.louds.select0(node);
+        let end = self.louds.select0(node + 1);
+        // Every node before the children has a 1, and every node up to `node` a 0.
+        let first = start - node;
+        first..first + (end - start - 1)
+    }
+
+    /// Returns the child of `node` with an edge starting with `b`.
+    fn child(&self, node: u64, b: u8) -> Option<u64> {
+        let children = self.children(node);
+        let labels = &self.first_bytes[children.start as usize..children.end as usize];
+        let i = labels.binary_search(&b).ok()?;
+        Some(children.start + i as u64)
+    }
+
+    fn parent(&self, node: u64) -> u64 {
+        self.louds.rank0(self.louds.select1(node)) - 1
+    }
+
+    /// Returns the bytes after the first byte of the edge to `node`.
+    fn tail(&self, node: u64) -> &[u8] {
+        let start = self.tail_offsets.get(node);
+        let end = self.tail_offsets.get(node + 1);
+        &self.tails[start as usize..end as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, StdRng};
+    use std::collections::BTreeSet;
+
+    fn gen_keys(rng: &mut StdRng, n: usize) -> BTreeSet<String> {
+        (0..n)
+            .map(|_| {
+                let len = rng.gen_range(0, 12);
+                (0..len)
+                    .map(|_| rng.gen_range(b'a', b'e') as char)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lookup_access() {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        for &n in &[0, 1, 10, 1000] {
+            let keys = gen_keys(&mut rng, n);
+            let keys_vec: Vec<&String> = keys.iter().collect();
+            let trie = LoudsTrie::new(&keys_vec);
+            assert_eq!(trie.len(), keys.len() as u64);
+
+            let mut ids = BTreeSet::new();
+            for key in &keys {
+                let id = trie.lookup(key).unwrap();
+                assert_eq!(&trie.access(id), key);
+                ids.insert(id);
+            }
+            assert!(ids.iter().copied().eq(0..keys.len() as u64));
+
+            for key in gen_keys(&mut rng, 200) {
+                assert_eq!(trie.lookup(&key).is_some(), keys.contains(&key), "{key}");
+            }
+        }
+    }
+
+    #[test]
+    fn searches() {
+        let mut rng: StdRng = SeedableRng::from_seed([1; 32]);
+        let keys = gen_keys(&mut rng, 500);
+        let keys_vec: Vec<&String> = keys.iter().collect();
+        let trie = LoudsTrie::new(&keys_vec);
+
+        for query in gen_keys(&mut rng, 200) {
+            let found: Vec<String> = trie
+                .predictive_search(&query)
+                .map(|id| trie.access(id))
+                .collect();
+            let expected: Vec<String> = keys
+                .iter()
+                .filter(|k| k.starts_with(&query))
+                .cloned()
+                .collect();
+            assert_eq!(found, expected, "predictive_search({query})");
+
+            let found: Vec<(String, usize)> = trie
+                .common_prefix_search(&query)
+                .map(|(id, len)| (trie.access(id), len))
+                .collect();
+            let expected: Vec<(String, usize)> = (0..=query.len())
+                .map(|len| &query[..len])
+                .filter(|prefix| keys.contains(*prefix))
+                .map(|prefix| (prefix.to_string(), prefix.len()))
+                .collect();
+            assert_eq!(found, expected, "common_prefix_search({query})");
+        }
+    }
+
+    #[test]
+    fn unicode() {
+        let keys = ["über", "übel", "ü", "日本", "日本語"];
+        let trie = LoudsTrie::new(&keys);
+        for key in keys {
+            assert_eq!(trie.access(trie.lookup(key).unwrap()), key);
+        }
+        assert_eq!(trie.predictive_search("日").count(), 2);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::bit_vector::BitVector;
+use crate::elias_fano::EliasFano;
+use crate::fid_iter::FidBitIter;
+
+/// A compressed non-decreasing sequence of integers with random access.
+///
+/// Values are stored with Elias-Fano encoding: the high bits in unary in a
+/// [`BitVector`], whose sampled `select1` gives constant-time access, and the
+/// low bits in a fixed-width [`BitArray`]. A sequence of `n` values below `u`
+/// takes about `2 + log(u / n)` bits per value.
+///
+/// It can also store the prefix sums of a sequence of lengths, such as
+/// document offsets, see [`from_lengths`].
+///
+/// [`BitArray`]: crate::BitArray
+/// [`from_lengths`]: MonotoneSequence::from_lengths
+///
+/// # Examples
+///
+/// ```
+/// # use fid::MonotoneSequence;
+/// let seq = MonotoneSequence::new(&[3, 8, 8, 20, 1000]);
+/// assert_eq!(seq.get(3), 20);
+/// assert_eq!(seq.next_geq(9), Some((3, 20)));
+/// assert_eq!(seq.prev_leq(9), Some((2, 8)));
+/// assert_eq!(seq.prev_leq(2), None);
+/// assert!(seq.iter().eq([3, 8, 8, 20, 1000]));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemDbg, mem_dbg::MemSize))]
+pub struct MonotoneSequence {
+    values: EliasFano,
+}
+
+impl MonotoneSequence {
+    /// Constructs a new [`MonotoneSequence`] from non-decreasing values.
+    ///
+    /// # Panics
+    /// * `values` is not sorted.
+    pub fn new(values: &[u64]) -> Self {
+        assert!(values.is_sorted(), "values are not sorted");
+        MonotoneSequence {
+            values: EliasFano::from_slice(values),
+        }
+    }
+
+    /// Constructs a new [`MonotoneSequence`] from the prefix sums of `lengths`.
+    ///
+    /// The sequence starts with 0 and has one more value than `lengths`,
+    /// so that the `i`-th length spans `get(i)..get(i + 1)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use fid::MonotoneSequence;
+    /// let offsets = MonotoneSequence::from_lengths([5, 0, 3]);
+    /// assert!(offsets.iter().eq([0, 5, 5, 8]));
+    /// assert_eq!(offsets.find(6), Some(2));
+    /// ```
+    ///
+    /// # Panics
+    /// * The sum of `lengths` overflows `u64`.
+    pub fn from_lengths<I: IntoIterator<Item = u64>>(lengths: I) -> Self {
+        let mut sum = 0u64;
+        let sums: Vec<u64> = std::iter::once(0)
+            .chain(lengths.into_iter().map(|len| {
+                sum = sum.checked_add(len).expect("sum of lengths overflows u64");
+                sum
+            }))
+            .collect();
+        Self::new(&sums)
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> u64 {
+        self.values.len()
+    }
+
+    /// Returns true if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the `i`-th value.
+    ///
+    /// # Panics
+    /// * `i` is out of bounds.
+    pub fn get(&self, i: u64) -> u64 {
+        self.values.get(i)
+    }
+
+    /// Returns the number of values less than `x`.
+    pub fn rank(&self, x: u64) -> u64 {
+        self.values.rank(x)
+    }
+
+    /// Returns the index and value of the first value greater than or equal to `x`.
+    pub fn next_geq(&self, x: u64) -> Option<(u64, u64)> {
+        let i = self.rank(x);
+        (i < self.len()).then(|| (i, self.get(i)))
+    }
+
+    /// Returns the index and value of the last value less than or equal to `x`.
+    pub fn prev_leq(&self, x: u64) -> Option<(u64, u64)> {
+        let i = match x.checked_add(1) {
+            Some(x) => self.rank(x),
+            None => self.len(),
+        };
+        (i > 0).then(|| (i - 1, self.get(i - 1)))
+    }
+
+    /// Returns the index of the last value less than or equal to `x`.
+    ///
+    /// For a sequence built with [`from_lengths`], this is the index of the
+    /// non-empty length spanning `x`.
+    ///
+    /// [`from_lengths`]: MonotoneSequence::from_lengths
+    pub fn find(&self, x: u64) -> Option<u64> {
+        self.prev_leq(x).map(|(i, _)| i)
+    }
+
+    /// Returns the bits storing the high part of the values in unary.
+    ///
+    /// The `i`-th value sets the bit at `(value >> low_size) + i`,
+    /// so `select1(i)` locates the high part of the `i`-th value.
+    pub fn high_bits(&self) -> &BitVector {
+        self.values.high_bits()
+    }
+
+    /// Returns the number of low bits of each value stored verbatim.
+    pub fn low_size(&self) -> u64 {
+        self.values.low_size()
+    }
+
+    /// Returns an iterator over the values.
+    pub fn iter(&self) -> MonotoneIter<'_> {
+        MonotoneIter {
+            values: &self.values,
+            bits: self.values.high_bits().iter(),
+            i: 0,
+            high: 0,
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<u64> {
+        self.iter().collect()
+    }
+}
+
+impl From<&[u64]> for MonotoneSequence {
+    fn from(values: &[u64]) -> Self {
+        Self::new(values)
+    }
+}
+
+impl FromIterator<u64> for MonotoneSequence {
+    fn from_iter<T: IntoIterator<Item = u64>>(iter: T) -> Self {
+        Self::new(&iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<'a> IntoIterator for &'a MonotoneSequence {
+    type Item = u64;
+
+    type IntoIter = MonotoneIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the values of a [`MonotoneSequence`].
+///
+/// Reads the high bits sequentially instead of selecting each value.
+#[derive(Debug, Clone)]
+pub struct MonotoneIter<'a> {
+    values: &'a EliasFano,
+    bits: FidBitIter<'a, BitVector>,
+    /// Index of the next value.
+    i: u64,
+    /// High part of the next value (number of 0s read).
+    high: u64,
+}
+
+impl Iterator for MonotoneIter<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i == self.values.len() {
+            return None;
+        }
+        while !self.bits.next()? {
+            self.high += 1;
+        }
+        let value = (self.high << self.values.low_size()) | self.values.low_bits(self.i);
+        self.i += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let size = (self.values.len() - self.i) as usize;
+        (size, Some(size))
+    }
+}
+
+impl ExactSizeIterator for MonotoneIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, StdRng};
+
+    #[test]
+    fn queries() {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        for &(n, max_gap) in &[
+            (0, 1),
+            (1, 1),
+            (1000, 1),
+            (1000, 3),
+            (3000, 1000),
+            (100, 1 << 40),
+        ] {
+            let mut value = 0;
+            let values: Vec<u64> = (0..n)
+                .map(|_| {
+                    value += rng.gen_range(0, max_gap);
+                    value
+                })
+                .collect();
+            let seq = MonotoneSequence::new(&values);
+            assert_eq!(seq.len(), n);
+            assert_eq!(seq.to_vec(), values);
+            assert_eq!(seq.iter().len(), values.len());
+
+            let max = values.last().map_or(0, |&v| v);
+            let queries = (0..500).map(|_| rng.gen_range(0, max + 2));
+            for x in queries.chain([0, max, max + 1, u64::MAX]) {
+                let i = values.partition_point(|&v| v < x);
+                let next = values.get(i).map(|&v| (i as u64, v));
+                assert_eq!(seq.next_geq(x), next, "next_geq({x})");
+
+                let j = values.partition_point(|&v| v <= x);
+                let prev = j.checked_sub(1).map(|j| (j as u64, values[j]));
+                assert_eq!(seq.prev_leq(x), prev, "prev_leq({x})");
+            }
+        }
+    }
+
+    #[test]
+    fn from_lengths() {
+        let lengths = [3, 0, 0, 7, 1, 0, 12];
+        let offsets = MonotoneSequence::from_lengths(lengths);
+        assert_eq!(offsets.to_vec(), [0, 3, 3, 3, 10, 11, 11, 23]);
+        for (doc, &len) in lengths.iter().enumerate() {
+            let start = offsets.get(doc as u64);
+            for x in start..start + len {
+                assert_eq!(offsets.find(x), Some(doc as u64));
+            }
+        }
+        assert_eq!(offsets.find(23), Some(7));
+    }
+
+    #[test]
+    fn max_value() {
+        let seq = MonotoneSequence::new(&[0, 5, u64::MAX, u64::MAX]);
+        assert_eq!(seq.to_vec(), [0, 5, u64::MAX, u64::MAX]);
+        assert_eq!(seq.next_geq(6), Some((2, u64::MAX)));
+        assert_eq!(seq.prev_leq(u64::MAX), Some((3, u64::MAX)));
+
+        let offsets = MonotoneSequence::from_lengths([u64::MAX - 1, 0, 1]);
+        assert_eq!(offsets.to_vec(), [0, u64::MAX - 1, u64::MAX - 1, u64::MAX]);
+        assert_eq!(offsets.find(u64::MAX - 1), Some(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "sum of lengths overflows u64")]
+    fn from_lengths_overflow() {
+        MonotoneSequence::from_lengths([u64::MAX, 1]);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::fid::FID;
+use crate::fid_iter::FidBitIter;
+
+/// A plain reference implementation of [`FID`] over a `Vec<bool>`.
+///
+/// Ranks are precomputed for every position and select reads the positions
+/// of each bit from a list, which makes the results obviously correct at the
+/// cost of about 130 bits per bit. It is intended for testing other [`FID`]s.
+///
+/// # Examples
+///
+/// ```
+/// # use fid::{bit_vec, NaiveFid, FID};
+/// let bits = [false, true, true, false, true, true, false, true];
+/// let naive = NaiveFid::from(bits.as_slice());
+/// let bv = bit_vec![false, true, true, false, true, true, false, true];
+/// for i in 0..=8 {
+///     assert_eq!(naive.rank1(i), bv.rank1(i));
+/// }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NaiveFid {
+    bits: Vec<bool>,
+    /// Number of 1s before each position.
+    ranks: Vec<u64>,
+    /// Positions of the 0s and 1s.
+    positions: [Vec<u64>; 2],
+}
+
+impl NaiveFid {
+    /// Constructs a new, empty [`NaiveFid`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the bits.
+    pub fn as_slice(&self) -> &[bool] {
+        &self.bits
+    }
+
+    pub fn iter(&self) -> FidBitIter<'_, Self> {
+        FidBitIter::new(self)
+    }
+
+    pub fn to_vec(&self) -> Vec<bool> {
+        self.bits.clone()
+    }
+}
+
+impl FID for NaiveFid {
+    fn len(&self) -> u64 {
+        self.bits.len() as u64
+    }
+
+    fn rank1(&self, i: u64) -> u64 {
+        self.ranks[i.min(self.len()) as usize]
+    }
+
+    fn rank0(&self, i: u64) -> u64 {
+        let i = i.min(self.len());
+        i - self.rank1(i)
+    }
+
+    fn select(&self, b: bool, r: u64) -> u64 {
+        let positions = &self.positions[b as usize];
+        *positions.get(r as usize).unwrap_or(&self.len())
+    }
+
+    fn select0(&self, r: u64) -> u64 {
+        self.select(false, r)
+    }
+
+    fn select1(&self, r: u64) -> u64 {
+        self.select(true, r)
+    }
+
+    fn get(&self, i: u64) -> bool {
+        self.bits[i as usize]
+    }
+
+    fn get_slice(&self, i: u64, size: u64) -> u64 {
+        debug_assert!(size <= 64);
+
+        let slice = &self.bits[i as usize..(i + size) as usize];
+        slice
+            .iter()
+            .rev()
+            .fold(0, |bits, &b| (bits << 1) | b as u64)
+    }
+
+    fn has_fast_get_slice(&self) -> bool {
+        true
+    }
+}
+
+impl<'i> IntoIterator for &'i NaiveFid {
+    type Item = bool;
+
+    type IntoIter = FidBitIter<'i, NaiveFid>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        FidBitIter::new(self)
+    }
+}
+
+impl From<Vec<bool>> for NaiveFid {
+    fn from(bits: Vec<bool>) -> Self {
+        let mut ranks = Vec::with_capacity(bits.len() + 1);
+        let mut positions = [Vec::new(), Vec::new()];
+        ranks.push(0);
+        for (i, &b) in bits.iter().enumerate() {
+            ranks.push(ranks[i] + b as u64);
+            positions[b as usize].push(i as u64);
+        }
+        NaiveFid {
+            bits,
+            ranks,
+            positions,
+        }
+    }
+}
+
+impl From<&[bool]> for NaiveFid {
+    fn from(bits: &[bool]) -> Self {
+        bits.to_vec().into()
+    }
+}
+
+impl FromIterator<bool> for NaiveFid {
+    fn from_iter<T: IntoIterator<Item = bool>>(iter: T) -> Self {
+        iter.into_iter().collect::<Vec<_>>().into()
+    }
+}
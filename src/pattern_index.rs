@@ -0,0 +1,294 @@
+use crate::bit_array::BitArray;
+use crate::bit_vector::{LBLOCK_WIDTH, SBLOCK_SIZE, SELECT_UNIT_NUM};
+use crate::coding::{ComboTable, SBLOCK_WIDTH};
+use crate::fid::FID;
+use crate::util::mask_u64;
+
+/// Read access to bits packed into 64-bit words.
+///
+/// Implemented by [`BitArray`], and by any [`FID`] wrapped in [`FidWords`],
+/// so a [`PatternIndex`] can be queried against the bits it was built from.
+pub trait ReadWords {
+    /// Returns the `i`-th 64-bit word, padded with zeros past the end.
+    fn read_word(&self, i: u64) -> u64;
+}
+
+impl ReadWords for BitArray {
+    fn read_word(&self, i: u64) -> u64 {
+        if i < self.block_len() as u64 {
+            self.get_word(i, SBLOCK_WIDTH)
+        } else {
+            0
+        }
+    }
+}
+
+/// Adapts any [`FID`] to [`ReadWords`], reading words with [`FID::get_slice`].
+///
+/// # Examples
+///
+/// ```
+/// # use fid::{bit_vec, FidWords, PatternIndex, FID};
+/// let bv = bit_vec![true, true, false, true, false];
+/// let ones = FidWords(bv.complement());
+/// let index = PatternIndex::new(&ones, bv.len(), [false, true]);
+/// assert_eq!(index.select(&ones, 0), 1);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct FidWords<T>(pub T);
+
+impl<T: FID> ReadWords for FidWords<T> {
+    fn read_word(&self, i: u64) -> u64 {
+        let start = i * SBLOCK_WIDTH;
+        let size = self.0.len().saturating_sub(start).min(SBLOCK_WIDTH);
+        if size == 0 {
+            0
+        } else {
+            self.0.get_slice(start, size)
+        }
+    }
+}
+
+/// An index for rank and select over occurrences of a 2-bit pattern.
+///
+/// An occurrence of the pattern `[a, b]` is a position `i` where bit `i` is `a`
+/// and bit `i + 1` is `b`. For example, the occurrences of `[true, false]` in a
+/// balanced parentheses sequence are its leaves.
+///
+/// The index does not store the bits themselves; queries take the bits it was built from.
+/// Like [`BitVector`], it counts occurrences per small block of `SBLOCK_WIDTH` bits
+/// and per large block of `LBLOCK_WIDTH` bits, and samples every `SELECT_UNIT_NUM`-th
+/// occurrence for select. This takes about 0.17 extra bits per bit.
+///
+/// [`BitVector`]: crate::BitVector
+///
+/// # Examples
+///
+/// ```
+/// # use fid::{bit_vec, FidWords, PatternIndex};
+/// // (()(()))
+/// let bv = FidWords(bit_vec![true, true, false, true, true, false, false, false]);
+/// let leaves = PatternIndex::new(&bv, 8, [true, false]);
+/// assert_eq!(leaves.count(), 2);
+/// assert_eq!(leaves.rank(&bv, 4), 1);
+/// assert_eq!(leaves.select(&bv, 1), 4);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemDbg, mem_dbg::MemSize))]
+pub struct PatternIndex {
+    /// Length of the indexed bits.
+    len: u64,
+    pattern: [bool; 2],
+    /// Number of occurrences.
+    count: u64,
+    /// Number of occurrences starting in each small block,
+    /// which are represented with `SBLOCK_SIZE` bits.
+    sblocks: BitArray,
+    /// Number of occurrences up to the end of each large block.
+    lblocks: Vec<u64>,
+    /// Large block of every `SELECT_UNIT_NUM`-th occurrence.
+    select_unit_pointers: Vec<usize>,
+}
+
+impl PatternIndex {
+    /// Constructs a new index of `pattern` over the first `len` bits of `bits`.
+    pub fn new<W: ReadWords + ?Sized>(bits: &W, len: u64, pattern: [bool; 2]) -> Self {
+        let sblock_len = len.div_ceil(SBLOCK_WIDTH);
+        let mut index = PatternIndex {
+            len,
+            pattern,
+            count: 0,
+            sblocks: BitArray::with_word_capacity(SBLOCK_SIZE, sblock_len),
+            lblocks: Vec::with_capacity(len.div_ceil(LBLOCK_WIDTH) as usize),
+            select_unit_pointers: Vec::new(),
+        };
+
+        for j in 0..sblock_len {
+            let k = index.match_word(bits, j).count_ones() as u64;
+            index.sblocks.set_word(j, SBLOCK_SIZE, k);
+            if index.count % SELECT_UNIT_NUM + k >= SELECT_UNIT_NUM {
+                let lblock_pos = j * SBLOCK_WIDTH / LBLOCK_WIDTH;
+                index.select_unit_pointers.push(lblock_pos as usize);
+            }
+            index.count += k;
+            if ((j + 1) * SBLOCK_WIDTH).is_multiple_of(LBLOCK_WIDTH) {
+                index.lblocks.push(index.count);
+            }
+        }
+        index.sblocks.shrink_to_fit();
+        index.lblocks.shrink_to_fit();
+        index.select_unit_pointers.shrink_to_fit();
+        index
+    }
+
+    /// Returns the length of the indexed bits.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if the indexed bits are empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the indexed pattern.
+    pub fn pattern(&self) -> [bool; 2] {
+        self.pattern
+    }
+
+    /// Returns the number of occurrences.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Compute the number of occurrences starting in `[0..i)`.
+    pub fn rank<W: ReadWords + ?Sized>(&self, bits: &W, i: u64) -> u64 {
+        let i = i.min(self.len);
+        let lblock_pos = i / LBLOCK_WIDTH;
+        let sblock_start_pos = lblock_pos * (LBLOCK_WIDTH / SBLOCK_WIDTH);
+        let sblock_end_pos = i / SBLOCK_WIDTH;
+
+        let mut rank = self.get_lblock(lblock_pos as usize);
+        for j in sblock_start_pos..sblock_end_pos {
+            rank += self.sblocks.get_word(j, SBLOCK_SIZE);
+        }
+        let offset = i % SBLOCK_WIDTH;
+        if offset != 0 {
+            let word = self.match_word(bits, sblock_end_pos);
+            rank += (word & mask_u64(offset)).count_ones() as u64;
+        }
+        rank
+    }
+
+    /// Locate the position of the `(r + 1)`-th occurrence.
+    ///
+    /// Returns the length if there is no such occurrence.
+    pub fn select<W: ReadWords + ?Sized>(&self, bits: &W, r: u64) -> u64 {
+        if self.count <= r {
+            return self.len;
+        }
+
+        let unit = (r / SELECT_UNIT_NUM) as usize;
+        let mut lblock_pos = *self
+            .select_unit_pointers
+            .get(unit.wrapping_sub(1))
+            .unwrap_or(&0);
+        while lblock_pos < self.lblocks.len() && self.lblocks[lblock_pos] <= r {
+            lblock_pos += 1;
+        }
+
+        let mut rank = self.get_lblock(lblock_pos);
+        let mut sblock_pos = lblock_pos as u64 * (LBLOCK_WIDTH / SBLOCK_WIDTH);
+        loop {
+            let k = self.sblocks.get_word(sblock_pos, SBLOCK_SIZE);
+            if r < rank + k {
+                break;
+            }
+            rank += k;
+            sblock_pos += 1;
+        }
+
+        let word = self.match_word(bits, sblock_pos);
+        let select = ComboTable::select0_raw(!word, (r - rank) as u32);
+        sblock_pos * SBLOCK_WIDTH + select as u64
+    }
+
+    fn get_lblock(&self, pos: usize) -> u64 {
+        *self.lblocks.get(pos.wrapping_sub(1)).unwrap_or(&0)
+    }
+
+    /// Returns the occurrences starting in the small block at `sblock_pos` as bits.
+    fn match_word<W: ReadWords + ?Sized>(&self, bits: &W, sblock_pos: u64) -> u64 {
+        // An occurrence needs the next bit, so the last bit never starts one.
+        let start = sblock_pos * SBLOCK_WIDTH;
+        let valid = self.len.saturating_sub(1).saturating_sub(start);
+        if valid == 0 {
+            return 0;
+        }
+
+        let word = bits.read_word(sblock_pos);
+        let next = if valid >= SBLOCK_WIDTH {
+            bits.read_word(sblock_pos + 1) & 1
+        } else {
+            0
+        };
+        let succ = (word >> 1) | (next << (SBLOCK_WIDTH - 1));
+        let [a, b] = self.pattern;
+        let first = if a { word } else { !word };
+        let second = if b { succ } else { !succ };
+        first & second & mask_u64(valid.min(SBLOCK_WIDTH))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bit_vector::BitVector;
+    use rand::{Rng, SeedableRng, StdRng};
+
+    const PATTERNS: [[bool; 2]; 4] = [[false, false], [false, true], [true, false], [true, true]];
+
+    #[test]
+    fn rank_select() {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        for &n in &[0, 1, 2, 64, 65, 1024, 10_000] {
+            for &p in &[0.01, 0.5, 0.99] {
+                let bits: Vec<bool> = (0..n).map(|_| rng.gen_bool(p)).collect();
+                let bv = FidWords(BitVector::from(bits.as_slice()));
+                let ba = BitArray::from(bits.as_slice());
+
+                for pattern in PATTERNS {
+                    let positions: Vec<u64> = bits
+                        .windows(2)
+                        .enumerate()
+                        .filter(|(_, w)| *w == pattern)
+                        .map(|(i, _)| i as u64)
+                        .collect();
+                    let index = PatternIndex::new(&bv, n, pattern);
+                    assert_eq!(index, PatternIndex::new(&ba, n, pattern));
+                    assert_eq!(index, PatternIndex::new(&FidWords(&bv.0), n, pattern));
+                    assert_eq!(index.count(), positions.len() as u64);
+
+                    let mut rank = 0;
+                    for i in 0..=n {
+                        assert_eq!(index.rank(&bv, i), rank);
+                        assert_eq!(index.rank(&ba, i), rank);
+                        if positions.get(rank as usize) == Some(&i) {
+                            rank += 1;
+                        }
+                    }
+                    for (r, &pos) in positions.iter().enumerate() {
+                        assert_eq!(index.select(&bv, r as u64), pos);
+                        assert_eq!(index.select(&ba, r as u64), pos);
+                    }
+                    assert_eq!(index.select(&bv, positions.len() as u64), n);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn views() {
+        let bits: Vec<bool> = (0..3000).map(|i| i % 7 < 3 || i % 11 == 0).collect();
+        let bv = BitVector::from(bits.as_slice());
+        let inverted: Vec<bool> = bits.iter().map(|b| !b).collect();
+
+        let check = |words: &dyn ReadWords, bits: &[bool]| {
+            let n = bits.len() as u64;
+            let ba = BitArray::from(bits);
+            for pattern in PATTERNS {
+                let index = PatternIndex::new(words, n, pattern);
+                assert_eq!(index, PatternIndex::new(&ba, n, pattern));
+                for r in 0..index.count() {
+                    let pos = index.select(words, r);
+                    assert_eq!(pos, index.select(&ba, r));
+                    assert_eq!(index.rank(words, pos), r);
+                }
+            }
+        };
+        check(&FidWords(&bv), &bits);
+        check(&FidWords(bv.slice(100..2900)), &bits[100..2900]);
+        check(&FidWords(bv.complement()), &inverted);
+    }
+}
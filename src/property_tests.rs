@@ -0,0 +1,178 @@
+//! Property tests with shrinking, focused on lengths and offsets around block boundaries.
+//!
+//! When a property fails, `proptest` shrinks the input and prints the minimal failing case.
+
+use crate::util::mask_u64;
+use crate::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+/// Lengths near multiples of the small block, large block and select unit widths.
+fn boundary_len() -> impl Strategy<Value = usize> {
+    let width = prop::sample::select(vec![64, 128, 1024, 2048, 4096, 8192]);
+    (width, -3isize..=3).prop_map(|(w, d)| (w as isize + d) as usize)
+}
+
+fn density() -> impl Strategy<Value = f64> {
+    prop_oneof![Just(0.0), Just(1.0), 0.0..=1.0]
+}
+
+/// Bits with a random density, either short or with a length near a block boundary.
+fn bits() -> impl Strategy<Value = Vec<bool>> {
+    let len = prop_oneof![0..300usize, boundary_len()];
+    (len, density()).prop_flat_map(|(len, p)| vec(prop::bool::weighted(p), len))
+}
+
+/// Checks rank and select at every position against [`NaiveFid`].
+fn check_rank_select<T: FID>(fid: &T, bits: &[bool]) -> Result<(), TestCaseError> {
+    let naive = NaiveFid::from(bits);
+    let n = naive.len();
+    prop_assert_eq!(fid.len(), n);
+    for i in 0..=n {
+        prop_assert_eq!(fid.rank1(i), naive.rank1(i), "rank1({})", i);
+    }
+    for b in [false, true] {
+        for r in 0..=naive.rank(b, n) {
+            prop_assert_eq!(fid.select(b, r), naive.select(b, r), "select({}, {})", b, r);
+        }
+    }
+    Ok(())
+}
+
+fn pack_words(bits: &[bool]) -> Vec<u64> {
+    bits.chunks(64)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .rev()
+                .fold(0, |word, &b| (word << 1) | b as u64)
+        })
+        .collect()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn bit_vector_push(bits in bits()) {
+        let mut bv = BitVector::new();
+        for &b in &bits {
+            bv.push(b);
+        }
+        check_rank_select(&bv, &bits)?;
+        prop_assert_eq!(bv.to_vec(), bits);
+    }
+
+    #[test]
+    fn bit_vector_construction(bits in bits(), chunk in 1..=64u64, parts in 1..5usize) {
+        let bv = BitVector::from(bits.as_slice());
+        prop_assert_eq!(&bits.iter().copied().collect::<BitVector>(), &bv);
+
+        let n = bits.len() as u64;
+        let words = pack_words(&bits);
+        prop_assert_eq!(&BitVector::from_words(&words, n), &bv);
+        prop_assert_eq!(&BitVector::from_words_in_parts(&words, n, parts), &bv);
+
+        let mut pushed = BitVector::new();
+        for (k, slice) in bits.chunks(chunk as usize).enumerate() {
+            let word = pack_words(slice)[0];
+            prop_assert_eq!(pushed.len(), k as u64 * chunk);
+            pushed.push_slice(word, slice.len() as u64);
+        }
+        prop_assert_eq!(&pushed, &bv);
+        check_rank_select(&bv, &bits)?;
+    }
+
+    #[test]
+    fn bit_vector_split_append(bits in bits(), at in any::<prop::sample::Index>()) {
+        let at = at.index(bits.len() + 1);
+        let bv = BitVector::from(bits.as_slice());
+        let mut head = bv.clone();
+        let tail = head.split_off(at as u64);
+        prop_assert_eq!(&head, &BitVector::from(&bits[..at]));
+        prop_assert_eq!(&tail, &BitVector::from(&bits[at..]));
+        head.append(&tail);
+        prop_assert_eq!(&head, &bv);
+    }
+
+    #[test]
+    fn bit_array_set_bit_slice(
+        background in vec(any::<bool>(), 0..300),
+        offset in 0..200u64,
+        bits in vec(any::<bool>(), 0..300),
+    ) {
+        let mut ba = BitArray::from(background.as_slice());
+        ba.set_bit_slice(offset, &bits);
+        let end = offset + bits.len() as u64;
+        for i in 0..end.max(background.len() as u64) {
+            let expected = if (offset..end).contains(&i) {
+                bits[(i - offset) as usize]
+            } else {
+                background.get(i as usize).copied().unwrap_or(false)
+            };
+            prop_assert_eq!(ba.get_bit(i), expected, "bit {}", i);
+        }
+    }
+
+    #[test]
+    fn bit_array_slice(
+        background in vec(any::<u64>(), 0..6),
+        i in 0..256u64,
+        size in 1..=64u64,
+        slice in any::<u64>(),
+    ) {
+        let mut ba = BitArray::from_blocks(background.clone());
+        let before = BitArray::from_blocks(background);
+        ba.set_slice(i, size, slice);
+        prop_assert_eq!(ba.get_slice(i, size), slice & mask_u64(size));
+
+        for j in 0..ba.len() {
+            if !(i..i + size).contains(&j) {
+                let expected = j < before.len() && before.get_bit(j);
+                prop_assert_eq!(ba.get_bit(j), expected, "bit {}", j);
+            }
+        }
+    }
+
+    #[test]
+    fn fid_bit_iter_nth(bits in bits(), skips in vec(0..200usize, 0..50)) {
+        let bv = BitVector::from(bits.as_slice());
+        let mut actual = bv.iter();
+        let mut expected = bits.iter().copied();
+        for skip in skips {
+            prop_assert_eq!(actual.len(), expected.len());
+            prop_assert_eq!(actual.nth(skip), expected.nth(skip));
+        }
+        prop_assert!(actual.eq(expected));
+    }
+
+    #[test]
+    fn monotone_sequence(
+        mut values in vec(prop_oneof![any::<u64>(), 0..1000u64, Just(u64::MAX)], 0..300),
+        queries in vec(any::<u64>(), 0..50),
+    ) {
+        values.sort_unstable();
+        let seq = MonotoneSequence::new(&values);
+        prop_assert_eq!(seq.to_vec(), values.clone());
+        for x in queries.into_iter().chain(values.iter().copied()).chain([0, u64::MAX]) {
+            let i = values.partition_point(|&v| v < x);
+            prop_assert_eq!(seq.next_geq(x), values.get(i).map(|&v| (i as u64, v)));
+            let j = values.partition_point(|&v| v <= x);
+            let prev = j.checked_sub(1).map(|j| (j as u64, values[j]));
+            prop_assert_eq!(seq.prev_leq(x), prev);
+        }
+    }
+
+    #[test]
+    fn monotone_sequence_from_lengths(mut lengths in vec(0..1000u64, 0..100), shift in 0..64u32) {
+        // The last length brings the sum up to `u64::MAX` when `shift` is 0.
+        let sum: u64 = lengths.iter().sum();
+        lengths.push((u64::MAX - sum) >> shift);
+        let offsets = MonotoneSequence::from_lengths(lengths.iter().copied());
+        prop_assert_eq!(offsets.len(), lengths.len() as u64 + 1);
+        for (i, &len) in lengths.iter().enumerate() {
+            let i = i as u64;
+            prop_assert_eq!(offsets.get(i + 1) - offsets.get(i), len);
+        }
+    }
+}
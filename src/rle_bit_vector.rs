@@ -0,0 +1,319 @@
+use crate::elias_fano::EliasFano;
+use crate::fid::FID;
+use crate::fid_iter::FidBitIter;
+use crate::util::mask_u64;
+
+/// A run-length encoded bit vector for highly clustered bitmaps.
+///
+/// The start of every run of 1s and the number of 1s up to the end of
+/// every run are stored in two Elias-Fano sequences. A vector with `r` runs
+/// of 1s takes about `2r * (2 + log(len / r))` bits, independent of its length.
+///
+/// `rank` and `select1` take constant time (plus a scan of one Elias-Fano bucket),
+/// while `select0` does a binary search over the runs in `O(log r)`.
+///
+/// # Examples
+///
+/// ```
+/// # use fid::{FID, RleBitVector};
+/// let rle: RleBitVector = (0..1_000_000).map(|i| (1000..3000).contains(&i)).collect();
+/// assert_eq!(rle.rank1(2000), 1000);
+/// assert_eq!(rle.select1(5), 1005);
+/// assert_eq!(rle.select0(1000), 3000);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemDbg, mem_dbg::MemSize))]
+pub struct RleBitVector {
+    /// Length of the vector (number of bits).
+    len: u64,
+    /// Number of 1s.
+    ones: u64,
+    /// Start position of each run of 1s.
+    starts: EliasFano,
+    /// Number of 1s up to the end of each run of 1s.
+    ends: EliasFano,
+}
+
+impl RleBitVector {
+    /// Constructs a new, empty [`RleBitVector`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs a new [`RleBitVector`] from the runs of any [`FID`].
+    pub fn from_fid<T: FID>(fid: &T) -> Self {
+        let mut builder = RunBuilder::default();
+        for (b, start, len) in fid.iter_runs() {
+            if b {
+                builder.push_run(start, len);
+            }
+        }
+        builder.build(fid.len())
+    }
+
+    /// Returns the number of runs of 1s.
+    pub fn one_runs(&self) -> u64 {
+        self.starts.len()
+    }
+
+    pub fn iter(&self) -> FidBitIter<'_, Self> {
+        FidBitIter::new(self)
+    }
+
+    pub fn to_vec(&self) -> Vec<bool> {
+        self.iter().collect()
+    }
+
+    /// Returns the number of 1s before run `j`.
+    fn ones_before(&self, j: u64) -> u64 {
+        if j == 0 {
+            0
+        } else {
+            self.ends.get(j - 1)
+        }
+    }
+
+    /// Returns the start and end positions of run `j`.
+    fn run(&self, j: u64) -> (u64, u64) {
+        let start = self.starts.get(j);
+        (start, start + self.ends.get(j) - self.ones_before(j))
+    }
+}
+
+#[derive(Default)]
+struct RunBuilder {
+    starts: Vec<u64>,
+    ends: Vec<u64>,
+    ones: u64,
+}
+
+impl RunBuilder {
+    fn push_run(&mut self, start: u64, len: u64) {
+        self.ones += len;
+        self.starts.push(start);
+        self.ends.push(self.ones);
+    }
+
+    fn build(self, len: u64) -> RleBitVector {
+        RleBitVector {
+            len,
+            ones: self.ones,
+            starts: EliasFano::from_slice(&self.starts),
+            ends: EliasFano::from_slice(&self.ends),
+        }
+    }
+}
+
+impl FID for RleBitVector {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn rank1(&self, i: u64) -> u64 {
+        let i = i.min(self.len);
+        let j = self.starts.rank(i);
+        if j == 0 {
+            return 0;
+        }
+        let (start, end) = self.run(j - 1);
+        self.ones_before(j - 1) + (i.min(end) - start)
+    }
+
+    fn select(&self, b: bool, r: u64) -> u64 {
+        if b {
+            self.select1(r)
+        } else {
+            self.select0(r)
+        }
+    }
+
+    fn select0(&self, r: u64) -> u64 {
+        if self.len - self.ones <= r {
+            return self.len;
+        }
+
+        // Find the number of runs with at most `r` zeros before them.
+        let (mut s, mut e) = (0, self.starts.len());
+        while s < e {
+            let m = (s + e) / 2;
+            let zeros = self.starts.get(m) - self.ones_before(m);
+            if zeros <= r {
+                s = m + 1;
+            } else {
+                e = m;
+            }
+        }
+        r + self.ones_before(s)
+    }
+
+    fn select1(&self, r: u64) -> u64 {
+        if self.ones <= r {
+            return self.len;
+        }
+        let j = self.ends.rank(r + 1);
+        self.starts.get(j) + (r - self.ones_before(j))
+    }
+
+    fn get(&self, i: u64) -> bool {
+        debug_assert!(i < self.len);
+
+        let j = self.starts.rank(i + 1);
+        j != 0 && i < self.run(j - 1).1
+    }
+
+    fn get_slice(&self, i: u64, size: u64) -> u64 {
+        debug_assert!(size <= 64);
+
+        let slice_end = i + size;
+        assert!(slice_end <= self.len);
+
+        let mut bits = 0;
+        let mut j = self.starts.rank(i + 1).saturating_sub(1);
+        while j < self.starts.len() {
+            let (start, end) = self.run(j);
+            if start >= slice_end {
+                break;
+            }
+            let lo = start.max(i);
+            let hi = end.min(slice_end);
+            if lo < hi {
+                bits |= mask_u64(hi - lo) << (lo - i);
+            }
+            j += 1;
+        }
+        bits
+    }
+
+    fn has_fast_get_slice(&self) -> bool {
+        true
+    }
+}
+
+impl<'i> IntoIterator for &'i RleBitVector {
+    type Item = bool;
+
+    type IntoIter = FidBitIter<'i, RleBitVector>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        FidBitIter::new(self)
+    }
+}
+
+impl From<&[bool]> for RleBitVector {
+    fn from(value: &[bool]) -> Self {
+        value.iter().copied().collect()
+    }
+}
+
+impl FromIterator<bool> for RleBitVector {
+    fn from_iter<T: IntoIterator<Item = bool>>(iter: T) -> Self {
+        let mut builder = RunBuilder::default();
+        let mut start = None;
+        let mut len = 0;
+        for b in iter {
+            match (b, start) {
+                (true, None) => start = Some(len),
+                (false, Some(s)) => {
+                    builder.push_run(s, len - s);
+                    start = None;
+                }
+                _ => {}
+            }
+            len += 1;
+        }
+        if let Some(s) = start {
+            builder.push_run(s, len - s);
+        }
+        builder.build(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BitVector;
+    use rand::{Rng, SeedableRng, StdRng};
+
+    const TEST_MAX_RUN: &[u64] = &[1, 2, 64, 1000, 10_000];
+
+    fn gen_clustered<F>(f: F)
+    where
+        F: Fn(u64, RleBitVector, BitVector),
+    {
+        for &max_run in TEST_MAX_RUN {
+            let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+            let n = 20_000;
+            let mut vec = Vec::new();
+            let mut b = rng.gen_bool(0.5);
+            while (vec.len() as u64) < n {
+                let len = rng.gen_range(1, max_run + 1);
+                vec.extend(std::iter::repeat_n(b, len as usize));
+                b = !b;
+            }
+            let rle = RleBitVector::from(vec.as_slice());
+            let bv = BitVector::from(vec.as_slice());
+            f(vec.len() as u64, rle, bv);
+        }
+    }
+
+    #[test]
+    fn rank1() {
+        gen_clustered(|n, rle, bv| {
+            for i in 0..=n {
+                assert_eq!(rle.rank1(i), bv.rank1(i));
+            }
+        });
+    }
+
+    #[test]
+    fn select() {
+        gen_clustered(|n, rle, bv| {
+            let ones = bv.rank1(n);
+            for r in 0..ones {
+                assert_eq!(rle.select1(r), bv.select1(r));
+            }
+            for r in 0..(n - ones) {
+                assert_eq!(rle.select0(r), bv.select0(r));
+            }
+            assert_eq!(rle.select1(ones), n);
+            assert_eq!(rle.select0(n - ones), n);
+        });
+    }
+
+    #[test]
+    fn get() {
+        gen_clustered(|n, rle, bv| {
+            for i in 0..n {
+                assert_eq!(rle.get(i), bv.get(i));
+            }
+            for size in [1, 7, 64] {
+                for i in 0..n / size {
+                    assert_eq!(rle.get_word(i, size), bv.get_word(i, size));
+                }
+            }
+            assert_eq!(rle.to_vec(), bv.to_vec());
+        });
+    }
+
+    #[test]
+    fn from_fid() {
+        gen_clustered(|_, rle, bv| {
+            assert_eq!(RleBitVector::from_fid(&bv), rle);
+        });
+    }
+
+    #[test]
+    fn empty() {
+        let rle = RleBitVector::new();
+        assert_eq!(rle.len(), 0);
+        assert_eq!(rle.rank1(0), 0);
+        assert_eq!(rle.select1(0), 0);
+        assert_eq!(rle.select0(0), 0);
+
+        let rle: RleBitVector = std::iter::repeat_n(false, 100).collect();
+        assert_eq!(rle.one_runs(), 0);
+        assert_eq!(rle.rank1(100), 0);
+        assert_eq!(rle.select0(99), 99);
+    }
+}
@@ -0,0 +1,307 @@
+use crate::bit_array::BitArray;
+use crate::bit_vector::BitVector;
+use crate::fid::FID;
+use crate::util::log2;
+
+/// Number of bits per block of the min tree.
+const BLOCK_WIDTH: u64 = 1024;
+
+/// Excess of a byte: total change, minimum prefix excess before each bit
+/// and the position of the rightmost minimum.
+#[derive(Clone, Copy)]
+struct ByteExcess {
+    total: i8,
+    min: i8,
+    pos: u8,
+}
+
+const BYTE_EXCESS: [ByteExcess; 256] = {
+    let mut table = [ByteExcess {
+        total: 0,
+        min: 0,
+        pos: 0,
+    }; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        let (mut e, mut min, mut pos) = (0i8, 0i8, 0u8);
+        let mut p = 0;
+        while p < 8 {
+            if e <= min {
+                (min, pos) = (e, p);
+            }
+            e += if (byte >> p) & 1 == 1 { 1 } else { -1 };
+            p += 1;
+        }
+        table[byte] = ByteExcess { total: e, min, pos };
+        byte += 1;
+    }
+    table
+};
+
+/// A range minimum query structure that does not keep the values.
+///
+/// The values are encoded in at most `2n` bits with a left-to-right minima stack:
+/// for each value, a 0 is written for every larger value popped from the stack,
+/// then a 1 for the value itself. The excess (number of 1s minus 0s) before the
+/// 1 of a value is then the depth of the stack below it, and the leftmost
+/// minimum of a range is the value of the rightmost minimum excess between the
+/// 1s of its ends. The minimum excess is found with a tree over blocks of
+/// `BLOCK_WIDTH` bits, which takes about `0.1` extra bits per bit, and a scan of
+/// at most two blocks. Queries take `O(log n)` time.
+///
+/// # Examples
+///
+/// ```
+/// # use fid::Rmq;
+/// let values = [5, 3, 8, 3, 1, 9, 2];
+/// let rmq = Rmq::new(&values);
+/// assert_eq!(rmq.argmin(0, 4), 1);
+/// assert_eq!(rmq.argmin(2, 4), 3);
+/// assert_eq!(rmq.argmin(0, 7), 4);
+/// assert_eq!(rmq.argmin(5, 7), 6);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemDbg, mem_dbg::MemSize))]
+pub struct Rmq {
+    /// Number of values.
+    len: u64,
+    /// Stack encoding of the values.
+    bits: BitVector,
+    /// Number of blocks, which are the leaves of the min tree.
+    block_len: u64,
+    /// Bits per node of the min tree.
+    node_size: u64,
+    /// Min tree over the excess in each block: node `k` has children `2k` and
+    /// `2k + 1`, and block `j` is node `block_len + j`.
+    tree: BitArray,
+}
+
+impl Rmq {
+    /// Constructs a new [`Rmq`] over `values`.
+    pub fn new<T: Ord>(values: &[T]) -> Self {
+        let len = values.len() as u64;
+        let mut words = Vec::with_capacity((2 * values.len()).div_ceil(64));
+        let mut bit_len = 0;
+        let mut push = |b: bool| {
+            if bit_len % 64 == 0 {
+                words.push(0);
+            }
+            *words.last_mut().unwrap() |= (b as u64) << (bit_len % 64);
+            bit_len += 1;
+        };
+
+        let mut stack: Vec<&T> = Vec::new();
+        for value in values {
+            while stack.last().is_some_and(|&top| top > value) {
+                stack.pop();
+                push(false);
+            }
+            stack.push(value);
+            push(true);
+        }
+        let bits = BitVector::from_words(&words, bit_len);
+
+        let block_len = bit_len.div_ceil(BLOCK_WIDTH);
+        let node_size = log2(len) as u64 + 1;
+        let mut rmq = Rmq {
+            len,
+            bits,
+            block_len,
+            node_size,
+            tree: BitArray::with_word_capacity(node_size, 2 * block_len),
+        };
+        for j in 0..block_len {
+            let start = j * BLOCK_WIDTH;
+            let end = (start + BLOCK_WIDTH).min(bit_len);
+            let (min, _) = rmq.scan(start, end);
+            rmq.tree.set_word(block_len + j, node_size, min);
+        }
+        for k in (1..block_len).rev() {
+            let min = rmq.node(2 * k).min(rmq.node(2 * k + 1));
+            rmq.tree.set_word(k, node_size, min);
+        }
+        rmq.tree.shrink_to_fit();
+        rmq
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the stack encoding of the values.
+    pub fn bits(&self) -> &BitVector {
+        &self.bits
+    }
+
+    /// Returns the position of the leftmost minimum value in `[l..r)`.
+    ///
+    /// # Panics
+    /// * The range is empty or out of bounds.
+    pub fn argmin(&self, l: u64, r: u64) -> u64 {
+        assert!(l < r && r <= self.len, "invalid range {l}..{r}");
+        if r - l == 1 {
+            return l;
+        }
+
+        // Rightmost minimum excess before the bits in `[start..end]`.
+        let start = self.bits.select1(l);
+        let end = self.bits.select1(r - 1) + 1;
+        let start_block = start.div_ceil(BLOCK_WIDTH);
+        let end_block = end / BLOCK_WIDTH;
+
+        let pos = if start_block >= end_block {
+            self.scan(start, end).1
+        } else {
+            let mut best = self.scan(start, start_block * BLOCK_WIDTH);
+            if let Some(block) = self.min_block(start_block, end_block) {
+                let block_start = block * BLOCK_WIDTH;
+                let block_end = (block_start + BLOCK_WIDTH).min(self.bits.len());
+                let candidate = self.scan(block_start, block_end);
+                if candidate.0 <= best.0 {
+                    best = candidate;
+                }
+            }
+            let candidate = self.scan(end_block * BLOCK_WIDTH, end);
+            if candidate.0 <= best.0 {
+                best = candidate;
+            }
+            best.1
+        };
+        self.bits.rank1(pos)
+    }
+
+    fn node(&self, k: u64) -> u64 {
+        self.tree.get_word(k, self.node_size)
+    }
+
+    /// Returns the rightmost block in `[start..end)` with the minimum excess.
+    fn min_block(&self, start: u64, end: u64) -> Option<u64> {
+        // Nodes covering the range, from left to right.
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        let (mut lo, mut hi) = (start + self.block_len, end + self.block_len);
+        while lo < hi {
+            if lo % 2 == 1 {
+                left.push(lo);
+                lo += 1;
+            }
+            if hi % 2 == 1 {
+                hi -= 1;
+                right.push(hi);
+            }
+            lo /= 2;
+            hi /= 2;
+        }
+
+        let mut best: Option<(u64, u64)> = None;
+        for k in left.into_iter().chain(right.into_iter().rev()) {
+            let min = self.node(k);
+            if best.is_none_or(|(best_min, _)| min <= best_min) {
+                best = Some((min, k));
+            }
+        }
+
+        let (min, mut k) = best?;
+        while k < self.block_len {
+            k = if self.node(2 * k + 1) == min {
+                2 * k + 1
+            } else {
+                2 * k
+            };
+        }
+        Some(k - self.block_len)
+    }
+
+    /// Returns the rightmost minimum excess before the bits in `[start..end)`
+    /// and its position, or a larger excess than any if the range is empty.
+    fn scan(&self, start: u64, end: u64) -> (u64, u64) {
+        let mut e = (2 * self.bits.rank1(start) - start) as i64;
+        let mut best = (i64::MAX, start);
+        let mut i = start;
+        while i < end {
+            let size = (end - i).min(64);
+            let mut word = self.bits.get_slice(i, size);
+            let mut k = 0;
+            while k < size {
+                if size - k >= 8 {
+                    let byte = BYTE_EXCESS[(word & 0xff) as usize];
+                    if e + byte.min as i64 <= best.0 {
+                        best = (e + byte.min as i64, i + k + byte.pos as u64);
+                    }
+                    e += byte.total as i64;
+                    word >>= 8;
+                    k += 8;
+                } else {
+                    if e <= best.0 {
+                        best = (e, i + k);
+                    }
+                    e += if word & 1 == 1 { 1 } else { -1 };
+                    word >>= 1;
+                    k += 1;
+                }
+            }
+            i += size;
+        }
+        (best.0 as u64, best.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, StdRng};
+
+    fn naive_argmin(values: &[u64], l: usize, r: usize) -> u64 {
+        let min = values[l..r].iter().min().unwrap();
+        (l + values[l..r].iter().position(|v| v == min).unwrap()) as u64
+    }
+
+    #[test]
+    fn argmin() {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        for &n in &[1, 2, 10, 600, 5000] {
+            for &max in &[1, 4, 1 << 20] {
+                let values: Vec<u64> = (0..n).map(|_| rng.gen_range(0, max)).collect();
+                let rmq = Rmq::new(&values);
+                assert_eq!(rmq.len(), n as u64);
+                assert!(rmq.bits().len() <= 2 * n as u64);
+
+                for _ in 0..2000 {
+                    let l = rng.gen_range(0, n);
+                    let r = if rng.gen_bool(0.5) {
+                        rng.gen_range(l + 1, n + 1)
+                    } else {
+                        rng.gen_range(l + 1, (l + 10).min(n) + 1)
+                    };
+                    let ans = naive_argmin(&values, l, r);
+                    assert_eq!(rmq.argmin(l as u64, r as u64), ans, "argmin({l}, {r})");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn monotone() {
+        let increasing: Vec<u64> = (0..3000).collect();
+        let decreasing: Vec<u64> = (0..3000).rev().collect();
+        let inc = Rmq::new(&increasing);
+        let dec = Rmq::new(&decreasing);
+        for (l, r) in [(0, 3000), (5, 2500), (1023, 1025), (2999, 3000)] {
+            assert_eq!(inc.argmin(l, r), l);
+            assert_eq!(dec.argmin(l, r), r - 1);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_range() {
+        Rmq::new(&[1, 2, 3]).argmin(1, 1);
+    }
+}
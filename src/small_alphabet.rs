@@ -0,0 +1,323 @@
+use crate::bit_array::BitArray;
+use crate::coding::ComboTable;
+use crate::util::mask_u64;
+
+const WORD_SIZE: u64 = 64;
+/// Bits of symbols per block.
+const DATA_WIDTH: u64 = 512;
+/// Bits per count relative to the superblock.
+const COUNT_SIZE: u64 = 16;
+/// Number of symbols per superblock, so that relative counts fit in `COUNT_SIZE` bits.
+const SUPERBLOCK_LEN: u64 = 1 << COUNT_SIZE;
+
+/// A sequence over an alphabet of up to 16 symbols with rank and select.
+///
+/// Symbols are packed with 1, 2 or 4 bits each into blocks of `DATA_WIDTH` bits.
+/// Each block is stored next to the number of occurrences of every symbol
+/// before it in its superblock of `SUPERBLOCK_LEN` symbols, so that `rank`
+/// reads one superblock count and one block. With `σ` symbols, this takes
+/// `w + 16σ / (DATA_WIDTH / w)` bits per symbol, e.g. 2.25 bits for DNA.
+///
+/// Unlike a wavelet tree, queries do not take `log σ` steps.
+///
+/// # Examples
+///
+/// ```
+/// # use fid::SmallAlphabetSequence;
+/// // ACGTTGCA
+/// let seq = SmallAlphabetSequence::new(4, &[0, 1, 2, 3, 3, 2, 1, 0]);
+/// assert_eq!(seq.access(3), 3);
+/// assert_eq!(seq.rank(2, 6), 2);
+/// assert_eq!(seq.select(1, 1), 6);
+/// assert_eq!(seq.select(1, 2), 8);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemDbg, mem_dbg::MemSize))]
+pub struct SmallAlphabetSequence {
+    len: u64,
+    sigma: u64,
+    /// Bits per symbol.
+    width: u64,
+    /// Bits of the counts at the start of each block, padded to whole words.
+    counts_size: u64,
+    /// Blocks of counts followed by symbols.
+    blocks: BitArray,
+    /// Number of occurrences of each symbol before each superblock, and in total.
+    superblocks: Vec<u64>,
+}
+
+impl SmallAlphabetSequence {
+    /// Constructs a new [`SmallAlphabetSequence`] over symbols in `0..sigma`.
+    ///
+    /// # Panics
+    /// * `sigma` is 0 or greater than 16.
+    /// * A symbol is not less than `sigma`.
+    pub fn new(sigma: u8, symbols: &[u8]) -> Self {
+        assert!((1..=16).contains(&sigma), "alphabet size must be in 1..=16");
+        let sigma = sigma as u64;
+        let width = match sigma {
+            1..=2 => 1,
+            3..=4 => 2,
+            _ => 4,
+        };
+        let counts_size = (sigma * COUNT_SIZE).next_multiple_of(WORD_SIZE);
+        let block_len = DATA_WIDTH / width;
+        let len = symbols.len() as u64;
+        let block_count = len.div_ceil(block_len);
+
+        let mut seq = SmallAlphabetSequence {
+            len,
+            sigma,
+            width,
+            counts_size,
+            blocks: BitArray::with_capacity(block_count * (counts_size + DATA_WIDTH)),
+            superblocks: Vec::with_capacity(((len / SUPERBLOCK_LEN + 2) * sigma) as usize),
+        };
+
+        let mut totals = vec![0; sigma as usize];
+        let mut relative = vec![0; sigma as usize];
+        for (j, block) in symbols.chunks(block_len as usize).enumerate() {
+            let start = j as u64 * block_len;
+            if start.is_multiple_of(SUPERBLOCK_LEN) {
+                seq.superblocks.extend_from_slice(&totals);
+                relative.fill(0);
+            }
+            let offset = seq.block_offset(j as u64);
+            for (c, &count) in relative.iter().enumerate() {
+                seq.blocks
+                    .set_slice(offset + c as u64 * COUNT_SIZE, COUNT_SIZE, count);
+            }
+            // Ensure the block is complete even if the symbols are not.
+            seq.blocks
+                .set_slice(offset + counts_size + DATA_WIDTH - 1, 1, 0);
+            for (k, &symbol) in block.iter().enumerate() {
+                assert!(
+                    (symbol as u64) < sigma,
+                    "symbol {symbol} is out of the alphabet"
+                );
+                let pos = offset + counts_size + k as u64 * width;
+                seq.blocks.set_slice(pos, width, symbol as u64);
+                relative[symbol as usize] += 1;
+                totals[symbol as usize] += 1;
+            }
+        }
+        seq.superblocks.extend_from_slice(&totals);
+        seq.blocks.shrink_to_fit();
+        seq.superblocks.shrink_to_fit();
+        seq
+    }
+
+    /// Returns the number of symbols.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if there are no symbols.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the size of the alphabet.
+    pub fn sigma(&self) -> u64 {
+        self.sigma
+    }
+
+    /// Returns the number of bits per symbol.
+    pub fn width(&self) -> u64 {
+        self.width
+    }
+
+    /// Returns the number of occurrences of `c`.
+    ///
+    /// # Panics
+    /// * `c` is not in the alphabet.
+    pub fn count(&self, c: u8) -> u64 {
+        assert!((c as u64) < self.sigma, "symbol {c} is out of the alphabet");
+        let superblock_count = self.superblocks.len() as u64 / self.sigma;
+        self.superblock(superblock_count - 1, c)
+    }
+
+    /// Returns the symbol at position `i`.
+    ///
+    /// # Panics
+    /// * `i` is out of bounds.
+    pub fn access(&self, i: u64) -> u8 {
+        assert!(i < self.len, "position {i} is out of bounds");
+        let block_len = self.block_len();
+        let offset = self.block_offset(i / block_len) + self.counts_size;
+        self.blocks
+            .get_slice(offset + i % block_len * self.width, self.width) as u8
+    }
+
+    /// Computes the number of occurrences of `c` in `[0..i)`.
+    ///
+    /// # Panics
+    /// * `c` is not in the alphabet.
+    pub fn rank(&self, c: u8, i: u64) -> u64 {
+        assert!((c as u64) < self.sigma, "symbol {c} is out of the alphabet");
+        let i = i.min(self.len);
+        let block_len = self.block_len();
+        let j = i / block_len;
+        let mut rank = self.superblock(i / SUPERBLOCK_LEN, c);
+        if !i.is_multiple_of(block_len) {
+            rank += self.relative(j, c) + self.count_in_block(j, c, i % block_len);
+        } else if !i.is_multiple_of(SUPERBLOCK_LEN) {
+            // The block at `i` may not exist, so read the counts of the previous one.
+            rank += self.relative(j - 1, c) + self.count_in_block(j - 1, c, block_len);
+        }
+        rank
+    }
+
+    /// Locates the position of the `(r + 1)`-th occurrence of `c`.
+    ///
+    /// Returns the length if there is no such occurrence.
+    ///
+    /// # Panics
+    /// * `c` is not in the alphabet.
+    pub fn select(&self, c: u8, r: u64) -> u64 {
+        assert!((c as u64) < self.sigma, "symbol {c} is out of the alphabet");
+        if r >= self.count(c) {
+            return self.len;
+        }
+
+        let superblock_count = self.superblocks.len() as u64 / self.sigma - 1;
+        let s = partition_point(0, superblock_count, |s| self.superblock(s, c) <= r) - 1;
+        let r = r - self.superblock(s, c);
+
+        let block_len = self.block_len();
+        let blocks_per_superblock = SUPERBLOCK_LEN / block_len;
+        let first = s * blocks_per_superblock;
+        let end = (first + blocks_per_superblock).min(self.len.div_ceil(block_len));
+        let j = partition_point(first, end, |j| self.relative(j, c) <= r) - 1;
+        let mut r = r - self.relative(j, c);
+
+        let offset = self.block_offset(j) + self.counts_size;
+        let symbols_per_word = WORD_SIZE / self.width;
+        for k in 0..DATA_WIDTH / WORD_SIZE {
+            let matches =
+                self.match_word(self.blocks.get_word(offset / WORD_SIZE + k, WORD_SIZE), c);
+            let count = matches.count_ones() as u64;
+            if r < count {
+                let i = ComboTable::select0_raw(!matches, r as u32) as u64 / self.width;
+                return j * block_len + k * symbols_per_word + i;
+            }
+            r -= count;
+        }
+        unreachable!("block counts are inconsistent")
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..self.len).map(|i| self.access(i))
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.iter().collect()
+    }
+
+    fn block_len(&self) -> u64 {
+        DATA_WIDTH / self.width
+    }
+
+    fn block_offset(&self, j: u64) -> u64 {
+        j * (self.counts_size + DATA_WIDTH)
+    }
+
+    fn superblock(&self, s: u64, c: u8) -> u64 {
+        self.superblocks[(s * self.sigma + c as u64) as usize]
+    }
+
+    /// Returns the occurrences of `c` before block `j` in its superblock.
+    fn relative(&self, j: u64, c: u8) -> u64 {
+        let offset = self.block_offset(j) + c as u64 * COUNT_SIZE;
+        self.blocks.get_slice(offset, COUNT_SIZE)
+    }
+
+    /// Returns the occurrences of `c` in the first `n` symbols of block `j`.
+    fn count_in_block(&self, j: u64, c: u8, n: u64) -> u64 {
+        let word_offset = (self.block_offset(j) + self.counts_size) / WORD_SIZE;
+        let bits = n * self.width;
+        let mut count = 0;
+        for k in 0..bits.div_ceil(WORD_SIZE) {
+            let word = self.blocks.get_word(word_offset + k, WORD_SIZE);
+            let size = (bits - k * WORD_SIZE).min(WORD_SIZE);
+            count += (self.match_word(word, c) & mask_u64(size)).count_ones() as u64;
+        }
+        count
+    }
+
+    /// Returns a word with the lowest bit of every symbol equal to `c` set.
+    fn match_word(&self, word: u64, c: u8) -> u64 {
+        let low_bits = match self.width {
+            1 => !0,
+            2 => 0x5555_5555_5555_5555,
+            _ => 0x1111_1111_1111_1111,
+        };
+        let mut diff = word ^ (low_bits * c as u64);
+        if self.width >= 2 {
+            diff |= diff >> 1;
+        }
+        if self.width == 4 {
+            diff |= diff >> 2;
+        }
+        !diff & low_bits
+    }
+}
+
+/// Returns the first `i` in `[lo..hi)` for which `pred` is false, or `hi`.
+fn partition_point<F: Fn(u64) -> bool>(mut lo: u64, mut hi: u64, pred: F) -> u64 {
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, StdRng};
+
+    #[test]
+    fn rank_select() {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        for &sigma in &[1u8, 2, 3, 4, 5, 16] {
+            for &n in &[0u64, 1, 255, 256, 257, 1000, 140_000] {
+                let symbols: Vec<u8> = (0..n).map(|_| rng.gen_range(0, sigma)).collect();
+                let seq = SmallAlphabetSequence::new(sigma, &symbols);
+                assert_eq!(seq.len(), n);
+                assert_eq!(seq.to_vec(), symbols);
+
+                let mut positions = vec![Vec::new(); sigma as usize];
+                for (i, &c) in symbols.iter().enumerate() {
+                    positions[c as usize].push(i as u64);
+                }
+                for c in 0..sigma {
+                    let positions = &positions[c as usize];
+                    assert_eq!(seq.count(c), positions.len() as u64);
+                    for _ in 0..300 {
+                        let i = rng.gen_range(0, n + 1);
+                        let rank = positions.partition_point(|&p| p < i) as u64;
+                        assert_eq!(seq.rank(c, i), rank, "rank({c}, {i})");
+                    }
+                    for _ in 0..positions.len().min(300) {
+                        let r = rng.gen_range(0, positions.len());
+                        assert_eq!(seq.select(c, r as u64), positions[r], "select({c}, {r})");
+                    }
+                    assert_eq!(seq.select(c, positions.len() as u64), n);
+                    assert_eq!(seq.rank(c, n), positions.len() as u64);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "symbol 3 is out of the alphabet")]
+    fn count_out_of_alphabet() {
+        SmallAlphabetSequence::new(3, &[0, 1, 2]).count(3);
+    }
+}
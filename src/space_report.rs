@@ -0,0 +1,113 @@
+use crate::coding::SBLOCK_WIDTH;
+use std::fmt;
+
+/// A breakdown of the space used by a [`BitVector`], see [`BitVector::space_report`].
+///
+/// All sizes are in bits and exclude unused capacity.
+///
+/// [`BitVector`]: crate::BitVector
+/// [`BitVector::space_report`]: crate::BitVector::space_report
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SpaceReport {
+    /// Length of the vector (number of bits).
+    pub len: u64,
+    /// Number of 1s.
+    pub ones: u64,
+    /// Classes of the small blocks.
+    pub sblocks: u64,
+    /// Enumerative codes of the small blocks.
+    pub indices: u64,
+    /// Ranks of the large blocks.
+    pub lblocks: u64,
+    /// Pointers of the large blocks to `indices`.
+    pub pointers: u64,
+    /// Select samples for both 0s and 1s.
+    pub select_units: u64,
+    /// Fixed-size fields, including the bits of the last partial small block.
+    pub header: u64,
+    /// Number of complete small blocks in each class (number of 1s).
+    pub class_histogram: Vec<u64>,
+}
+
+impl SpaceReport {
+    /// Returns the total number of bits.
+    pub fn total_bits(&self) -> u64 {
+        self.sblocks + self.indices + self.lblocks + self.pointers + self.select_units + self.header
+    }
+
+    /// Returns the number of bits used per bit of the vector, or 0 if it is empty.
+    pub fn bits_per_bit(&self) -> f64 {
+        self.per_bit(self.total_bits() as f64)
+    }
+
+    /// Returns the empirical zero-order entropy `H0` per bit.
+    pub fn entropy(&self) -> f64 {
+        if self.len == 0 {
+            return 0.0;
+        }
+        let p = self.ones as f64 / self.len as f64;
+        let h = |x: f64| if x > 0.0 { -x * x.log2() } else { 0.0 };
+        h(p) + h(1.0 - p)
+    }
+
+    /// Returns the empirical zero-order entropy of the whole vector (`nH0`) in bits.
+    pub fn entropy_bits(&self) -> f64 {
+        self.entropy() * self.len as f64
+    }
+
+    /// Divides `bits` by the length, or returns 0 if the vector is empty.
+    fn per_bit(&self, bits: f64) -> f64 {
+        if self.len == 0 {
+            0.0
+        } else {
+            bits / self.len as f64
+        }
+    }
+}
+
+impl fmt::Display for SpaceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "length:       {} bits ({} ones, p = {:.4})",
+            self.len,
+            self.ones,
+            self.per_bit(self.ones as f64)
+        )?;
+
+        let components = [
+            ("sblocks", self.sblocks),
+            ("indices", self.indices),
+            ("lblocks", self.lblocks),
+            ("pointers", self.pointers),
+            ("select units", self.select_units),
+            ("header", self.header),
+            ("total", self.total_bits()),
+        ];
+        for (name, bits) in components {
+            let label = format!("{name}:");
+            writeln!(
+                f,
+                "{label:<13} {bits} bits ({:.4} bits/bit)",
+                self.per_bit(bits as f64)
+            )?;
+        }
+
+        let entropy = self.entropy_bits();
+        writeln!(
+            f,
+            "entropy nH0:  {:.0} bits ({:.4} bits/bit, {:.4} bits/bit overhead)",
+            entropy,
+            self.entropy(),
+            self.per_bit(self.total_bits() as f64 - entropy)
+        )?;
+
+        write!(f, "classes:     ")?;
+        for (k, &count) in self.class_histogram.iter().enumerate() {
+            if count != 0 {
+                write!(f, " {k}/{SBLOCK_WIDTH}: {count}")?;
+            }
+        }
+        Ok(())
+    }
+}
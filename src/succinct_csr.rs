@@ -0,0 +1,247 @@
+use crate::bit_array::BitArray;
+use crate::bit_vector::BitVector;
+use crate::fid::FID;
+use crate::util::log2;
+use std::ops::Range;
+
+/// A compressed sparse row graph with succinct offsets.
+///
+/// Instead of an array of offsets, node boundaries are stored in unary in a
+/// [`BitVector`]: a 1 for every node followed by a 0 for every edge leaving it,
+/// and a final 1. The edges of `u` then span `select1(u) - u..select1(u + 1) - u - 1`,
+/// which takes about 2 bits per edge and node. Neighbors are stored in a
+/// [`BitArray`] with `ceil(log(node_count))` bits each.
+///
+/// # Examples
+///
+/// ```
+/// # use fid::SuccinctCsr;
+/// let graph = SuccinctCsr::from_edges(4, &[(0, 1), (0, 3), (2, 0), (2, 1), (2, 3)]);
+/// assert_eq!(graph.degree(2), 3);
+/// assert!(graph.neighbors(0).eq([1, 3]));
+/// assert!(graph.neighbors(1).eq([]));
+/// assert!(graph.has_edge(2, 1));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemDbg, mem_dbg::MemSize))]
+pub struct SuccinctCsr {
+    node_count: u64,
+    edge_count: u64,
+    /// A 1 for every node followed by a 0 for each of its edges, and a final 1.
+    offsets: BitVector,
+    /// Bits per neighbor.
+    width: u64,
+    neighbors: BitArray,
+}
+
+impl SuccinctCsr {
+    /// Constructs a new [`SuccinctCsr`] over `node_count` nodes from a list of edges
+    /// sorted by source node.
+    ///
+    /// Neighbors keep the order of the edges.
+    /// [`has_edge`] expects them to be sorted too.
+    ///
+    /// # Panics
+    /// * The edges are not sorted by source node.
+    /// * An edge is out of bounds.
+    ///
+    /// [`has_edge`]: SuccinctCsr::has_edge
+    pub fn from_edges(node_count: u64, edges: &[(u64, u64)]) -> Self {
+        assert!(
+            edges.is_sorted_by_key(|&(u, _)| u),
+            "edges are not sorted by source node"
+        );
+
+        let edge_count = edges.len() as u64;
+        let width = log2(node_count.saturating_sub(1)) as u64 + 1;
+        let mut offsets = BitVector::with_capacity(node_count + edge_count + 1);
+        let mut neighbors = BitArray::with_word_capacity(width, edge_count);
+        let mut u = 0;
+        offsets.push(true);
+        for (i, &(src, dst)) in edges.iter().enumerate() {
+            assert!(
+                src < node_count && dst < node_count,
+                "edge ({src}, {dst}) is out of bounds"
+            );
+            while u < src {
+                offsets.push(true);
+                u += 1;
+            }
+            offsets.push(false);
+            neighbors.set_word(i as u64, width, dst);
+        }
+        while u < node_count {
+            offsets.push(true);
+            u += 1;
+        }
+        offsets.shrink_to_fit();
+        neighbors.shrink_to_fit();
+
+        SuccinctCsr {
+            node_count,
+            edge_count,
+            offsets,
+            width,
+            neighbors,
+        }
+    }
+
+    /// Returns the number of nodes.
+    pub fn node_count(&self) -> u64 {
+        self.node_count
+    }
+
+    /// Returns the number of edges.
+    pub fn edge_count(&self) -> u64 {
+        self.edge_count
+    }
+
+    /// Returns the indices of the edges leaving `u`.
+    ///
+    /// # Panics
+    /// * `u` is out of bounds.
+    pub fn edge_range(&self, u: u64) -> Range<u64> {
+        assert!(u < self.node_count, "node {u} is out of bounds");
+        let start = self.offsets.select1(u) - u;
+        let end = self.offsets.select1(u + 1) - u - 1;
+        start..end
+    }
+
+    /// Returns the number of edges leaving `u`.
+    ///
+    /// # Panics
+    /// * `u` is out of bounds.
+    pub fn degree(&self, u: u64) -> u64 {
+        let range = self.edge_range(u);
+        range.end - range.start
+    }
+
+    /// Returns the target of the `i`-th edge.
+    ///
+    /// # Panics
+    /// * `i` is out of bounds.
+    pub fn target(&self, i: u64) -> u64 {
+        assert!(i < self.edge_count, "edge {i} is out of bounds");
+        self.neighbors.get_word(i, self.width)
+    }
+
+    /// Returns the source of the `i`-th edge.
+    ///
+    /// # Panics
+    /// * `i` is out of bounds.
+    pub fn source(&self, i: u64) -> u64 {
+        assert!(i < self.edge_count, "edge {i} is out of bounds");
+        self.offsets.rank1(self.offsets.select0(i)) - 1
+    }
+
+    /// Returns an iterator over the neighbors of `u`.
+    ///
+    /// # Panics
+    /// * `u` is out of bounds.
+    pub fn neighbors(&self, u: u64) -> CsrNeighbors<'_> {
+        CsrNeighbors {
+            graph: self,
+            range: self.edge_range(u),
+        }
+    }
+
+    /// Returns true if there is an edge from `u` to `v`,
+    /// with a binary search over the neighbors of `u`.
+    ///
+    /// # Panics
+    /// * `u` is out of bounds.
+    pub fn has_edge(&self, u: u64, v: u64) -> bool {
+        let Range { mut start, mut end } = self.edge_range(u);
+        while start < end {
+            let mid = start + (end - start) / 2;
+            let w = self.target(mid);
+            if w == v {
+                return true;
+            } else if w < v {
+                start = mid + 1;
+            } else {
+                end = mid;
+            }
+        }
+        false
+    }
+
+    /// Returns an iterator over all edges as `(source, target)`.
+    pub fn edges(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        (0..self.node_count).flat_map(move |u| self.neighbors(u).map(move |v| (u, v)))
+    }
+}
+
+/// Iterator over the neighbors of a node in a [`SuccinctCsr`].
+#[derive(Debug, Clone)]
+pub struct CsrNeighbors<'a> {
+    graph: &'a SuccinctCsr,
+    range: Range<u64>,
+}
+
+impl Iterator for CsrNeighbors<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.range.next().map(|i| self.graph.target(i))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.range.nth(n).map(|i| self.graph.target(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let size = (self.range.end - self.range.start) as usize;
+        (size, Some(size))
+    }
+}
+
+impl DoubleEndedIterator for CsrNeighbors<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.range.next_back().map(|i| self.graph.target(i))
+    }
+}
+
+impl ExactSizeIterator for CsrNeighbors<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, StdRng};
+
+    #[test]
+    fn neighbors() {
+        let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+        for &(n, m) in &[(0, 0), (1, 0), (1, 3), (5, 20), (100, 30), (1000, 10_000)] {
+            let mut edges: Vec<(u64, u64)> = (0..m)
+                .map(|_| (rng.gen_range(0, n), rng.gen_range(0, n)))
+                .collect();
+            edges.sort_unstable();
+            let graph = SuccinctCsr::from_edges(n, &edges);
+            assert_eq!(graph.node_count(), n);
+            assert_eq!(graph.edge_count(), m as u64);
+            assert!(graph.edges().eq(edges.iter().copied()));
+
+            for u in 0..n {
+                let expected: Vec<u64> = edges.iter().filter(|e| e.0 == u).map(|e| e.1).collect();
+                assert_eq!(graph.degree(u), expected.len() as u64);
+                assert_eq!(graph.neighbors(u).len(), expected.len());
+                assert!(graph.neighbors(u).eq(expected.iter().copied()));
+                assert!(graph.neighbors(u).rev().eq(expected.iter().rev().copied()));
+                let v = rng.gen_range(0, n);
+                assert_eq!(graph.has_edge(u, v), expected.contains(&v));
+            }
+            for (i, &(u, v)) in edges.iter().enumerate() {
+                assert_eq!(graph.source(i as u64), u);
+                assert_eq!(graph.target(i as u64), v);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn unsorted() {
+        SuccinctCsr::from_edges(3, &[(1, 0), (0, 2)]);
+    }
+}